@@ -1,21 +1,14 @@
 use anyhow::{anyhow, Result};
-use async_trait::async_trait;
 use clap::Parser;
-use reqwest::Url;
-use std::fs::File;
 
 use scroll_proving_sdk::{
-    config::Config as SdkConfig,
-    prover::{
-        proving_service::{
-            GetVkRequest, GetVkResponse, ProveRequest, ProveResponse, QueryTaskRequest,
-            QueryTaskResponse,
-        },
-        ProverBuilder, ProvingService,
-    },
+    config::{load_layered_config, Config as SdkConfig, ProvingServiceConfig},
+    prover::{HttpProvingService, ProverBuilder},
     utils::init_tracing,
 };
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::info;
 
 #[derive(Parser, Debug)]
 #[clap(disable_version_flag = true)]
@@ -23,28 +16,22 @@ struct Args {
     /// Path of config file
     #[arg(long = "config", default_value = "config.json")]
     config_file: String,
+    /// Path of an optional config file merged on top of the main one
+    #[arg(long = "config-overlay")]
+    config_overlay_file: Option<String>,
+    /// Validate the config file and exit
+    #[arg(long = "check-config")]
+    check_config: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CloudProverConfig {
     pub sdk_config: SdkConfig,
-    pub base_url: String,
-    pub api_key: String,
+    #[serde(flatten)]
+    pub proving_service: ProvingServiceConfig,
 }
 
 impl CloudProverConfig {
-    pub fn from_reader<R>(reader: R) -> Result<Self>
-    where
-        R: std::io::Read,
-    {
-        serde_json::from_reader(reader).map_err(|e| anyhow!(e))
-    }
-
-    pub fn from_file(file_name: String) -> Result<Self> {
-        let file = File::open(file_name)?;
-        Self::from_reader(&file)
-    }
-
     fn get_env_var(key: &str) -> Result<Option<String>> {
         std::env::var_os(key)
             .map(|val| {
@@ -55,61 +42,55 @@ impl CloudProverConfig {
             .transpose()
     }
 
-    pub fn from_file_and_env(file_name: String) -> Result<Self> {
-        let mut cfg = Self::from_file(file_name)?;
+    pub fn from_files_and_env(
+        file_name: String,
+        overlay_file_name: Option<String>,
+    ) -> Result<Self> {
+        let mut cfg: Self = load_layered_config(file_name, overlay_file_name)?;
+        cfg.sdk_config.override_with_env()?;
 
         if let Some(val) = Self::get_env_var("PROVING_SERVICE_BASE_URL")? {
-            cfg.base_url = val;
+            cfg.proving_service.base_url = val;
         }
 
         if let Some(val) = Self::get_env_var("PROVING_SERVICE_API_KEY")? {
-            cfg.api_key = val;
+            cfg.proving_service.api_key = val;
         }
 
         Ok(cfg)
     }
 }
 
-struct CloudProver {
-    base_url: Url,
-    api_key: String,
-}
-
-#[async_trait]
-impl ProvingService for CloudProver {
-    fn is_local(&self) -> bool {
-        false
-    }
-    async fn get_vks(&self, req: GetVkRequest) -> GetVkResponse {
-        todo!()
-    }
-    async fn prove(&mut self, req: ProveRequest) -> ProveResponse {
-        todo!()
-    }
-    async fn query_task(&mut self, req: QueryTaskRequest) -> QueryTaskResponse {
-        todo!()
-    }
-}
-
-impl CloudProver {
-    pub fn new(cfg: CloudProverConfig) -> Self {
-        let base_url = Url::parse(&cfg.base_url).expect("cannot parse cloud prover base_url");
-        Self {
-            base_url,
-            api_key: cfg.api_key,
-        }
-    }
-}
-
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     init_tracing();
 
     let args = Args::parse();
-    let cfg = CloudProverConfig::from_file_and_env(args.config_file)?;
+    let cfg = CloudProverConfig::from_files_and_env(
+        args.config_file.clone(),
+        args.config_overlay_file.clone(),
+    )?;
     let sdk_config = cfg.sdk_config.clone();
-    let cloud_prover = CloudProver::new(cfg);
-    let prover = ProverBuilder::new(sdk_config, cloud_prover).build().await?;
+    if args.check_config {
+        sdk_config.validate()?;
+        info!("config is valid");
+        return Ok(());
+    }
+    let cloud_prover = HttpProvingService::new(cfg.proving_service)?;
+    let config_files = std::iter::once(&args.config_file)
+        .chain(&args.config_overlay_file)
+        .map(PathBuf::from)
+        .collect();
+    let prover = ProverBuilder::new(sdk_config, cloud_prover)
+        .with_config_reload(config_files, move || {
+            let cfg = CloudProverConfig::from_files_and_env(
+                args.config_file.clone(),
+                args.config_overlay_file.clone(),
+            )?;
+            Ok(cfg.sdk_config)
+        })
+        .build()
+        .await?;
 
     prover.run().await;
 
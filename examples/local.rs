@@ -1,8 +1,8 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use async_trait::async_trait;
 use clap::Parser;
 use scroll_proving_sdk::{
-    config::Config as SdkConfig,
+    config::{load_layered_config, Config as SdkConfig},
     prover::{
         proving_service::{
             GetVkRequest, GetVkResponse, ProveRequest, ProveResponse, QueryTaskRequest,
@@ -13,7 +13,8 @@ use scroll_proving_sdk::{
     utils::init_tracing,
 };
 use serde::{Deserialize, Serialize};
-use std::fs::File;
+use std::path::PathBuf;
+use tracing::info;
 
 #[derive(Parser, Debug)]
 #[clap(disable_version_flag = true)]
@@ -21,6 +22,12 @@ struct Args {
     /// Path of config file
     #[arg(long = "config", default_value = "config.json")]
     config_file: String,
+    /// Path of an optional config file merged on top of the main one
+    #[arg(long = "config-overlay")]
+    config_overlay_file: Option<String>,
+    /// Validate the config file and exit
+    #[arg(long = "check-config")]
+    check_config: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -31,20 +38,12 @@ pub struct LocalProverConfig {
 }
 
 impl LocalProverConfig {
-    pub fn from_reader<R>(reader: R) -> Result<Self>
-    where
-        R: std::io::Read,
-    {
-        serde_json::from_reader(reader).map_err(|e| anyhow!(e))
-    }
-
-    pub fn from_file(file_name: String) -> Result<Self> {
-        let file = File::open(file_name)?;
-        Self::from_reader(&file)
-    }
-
-    pub fn from_file_and_env(file_name: String) -> Result<Self> {
-        let cfg = Self::from_file(file_name)?;
+    pub fn from_files_and_env(
+        file_name: String,
+        overlay_file_name: Option<String>,
+    ) -> Result<Self> {
+        let mut cfg: Self = load_layered_config(file_name, overlay_file_name)?;
+        cfg.sdk_config.override_with_env()?;
         Ok(cfg)
     }
 }
@@ -59,10 +58,10 @@ impl ProvingService for LocalProver {
     async fn get_vks(&self, req: GetVkRequest) -> GetVkResponse {
         todo!()
     }
-    async fn prove(&mut self, req: ProveRequest) -> ProveResponse {
+    async fn prove(&self, req: ProveRequest) -> ProveResponse {
         todo!()
     }
-    async fn query_task(&mut self, req: QueryTaskRequest) -> QueryTaskResponse {
+    async fn query_task(&self, req: QueryTaskRequest) -> QueryTaskResponse {
         todo!()
     }
 }
@@ -78,10 +77,31 @@ async fn main() -> anyhow::Result<()> {
     init_tracing();
 
     let args = Args::parse();
-    let cfg = LocalProverConfig::from_file_and_env(args.config_file)?;
+    let cfg = LocalProverConfig::from_files_and_env(
+        args.config_file.clone(),
+        args.config_overlay_file.clone(),
+    )?;
     let sdk_config = cfg.sdk_config.clone();
+    if args.check_config {
+        sdk_config.validate()?;
+        info!("config is valid");
+        return Ok(());
+    }
     let local_prover = LocalProver::new(cfg);
-    let prover = ProverBuilder::new(sdk_config, local_prover).build().await?;
+    let config_files = std::iter::once(&args.config_file)
+        .chain(&args.config_overlay_file)
+        .map(PathBuf::from)
+        .collect();
+    let prover = ProverBuilder::new(sdk_config, local_prover)
+        .with_config_reload(config_files, move || {
+            let cfg = LocalProverConfig::from_files_and_env(
+                args.config_file.clone(),
+                args.config_overlay_file.clone(),
+            )?;
+            Ok(cfg.sdk_config)
+        })
+        .build()
+        .await?;
 
     prover.run().await;
 
@@ -1,23 +1,127 @@
 use crate::{
     coordinator_handler::ProverType,
-    prover::{CircuitType, ProofType},
+    prover::{CircuitType, InputCompression, ProofType},
 };
 use anyhow::{anyhow, Result};
 use dotenv::dotenv;
-use serde::{Deserialize, Serialize};
-use serde_json;
-use std::fs::File;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{self, json, Value};
+use std::{fmt, net::SocketAddr, path::Path};
+use url::Url;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
     pub prover_name_prefix: String,
     pub keys_dir: String,
+    /// Stores the prover keys in `keys_dir` as encrypted keystores instead of plain hex.
+    #[serde(default)]
+    pub keystore: Option<KeystoreConfig>,
+    /// Whether missing prover keys are generated. If not, a missing key fails startup.
+    #[serde(default = "default_generate_missing_keys")]
+    pub generate_missing_keys: bool,
+    /// Signs with keys held by a remote signing service instead of the keys in `keys_dir`.
+    #[serde(default)]
+    pub remote_signer: Option<RemoteSignerConfig>,
     pub db_path: Option<String>,
-    pub coordinator: CoordinatorConfig,
+    /// Coordinator of the single network served when `networks` is empty.
+    #[serde(default)]
+    pub coordinator: Option<CoordinatorConfig>,
     pub l2geth: Option<L2GethConfig>,
     pub prover: ProverConfig,
+    /// Networks served by this prover, all sharing its proving service. When empty, the prover
+    /// serves a single network made of `coordinator`, `l2geth` and `prover`.
+    #[serde(default)]
+    pub networks: Vec<NetworkConfig>,
     #[serde(default = "default_health_listener_addr")]
     pub health_listener_addr: String,
+    /// Serves the task history at `/tasks` on the health listener. The endpoint is not
+    /// authenticated, so only enable it where the listener is not publicly reachable.
+    #[serde(default)]
+    pub task_history_endpoint: bool,
+    #[serde(default)]
+    pub block_cache: BlockCacheConfig,
+}
+
+/// On-disk cache of the block traces and witnesses fetched from l2geth, kept in `db_path`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BlockCacheConfig {
+    /// Off by default: the cache is bounded by age only, so its disk usage grows with the number
+    /// of blocks proved within `max_age_sec`.
+    pub enabled: bool,
+    /// Entries older than this are ignored and eventually evicted.
+    pub max_age_sec: u64,
+    /// How often expired entries are evicted.
+    pub eviction_interval_sec: u64,
+}
+
+impl Default for BlockCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_age_sec: 24 * 3600,
+            eviction_interval_sec: 3600,
+        }
+    }
+}
+
+/// Password source for the prover keystores. The `password_env` env var takes precedence over
+/// `password_file`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KeystoreConfig {
+    #[serde(default = "default_keystore_password_env")]
+    pub password_env: String,
+    #[serde(default)]
+    pub password_file: Option<String>,
+}
+
+impl KeystoreConfig {
+    pub fn password(&self) -> Result<String> {
+        if let Some(password) = Config::get_env_var(&self.password_env)? {
+            return Ok(password);
+        }
+        let Some(password_file) = &self.password_file else {
+            return Err(anyhow!(
+                "keystore password not found, set the {} env var or password_file",
+                self.password_env
+            ));
+        };
+        let password = std::fs::read_to_string(password_file)
+            .map_err(|e| anyhow!("failed to read keystore password file {password_file}: {e}"))?;
+        Ok(password.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+/// Connection settings for a signing service speaking the protocol of
+/// [`RemoteSigner`](crate::coordinator_handler::RemoteSigner).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoteSignerConfig {
+    pub base_url: String,
+    /// Public key of each worker, in worker order across all networks.
+    pub public_keys: Vec<String>,
+    #[serde(default = "default_remote_signer_retry_count")]
+    pub retry_count: u32,
+    #[serde(default = "default_remote_signer_retry_wait_time_sec")]
+    pub retry_wait_time_sec: u64,
+    #[serde(default = "default_remote_signer_connection_timeout_sec")]
+    pub connection_timeout_sec: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NetworkConfig {
+    /// Used in prover names and logs, and as the default keys subdirectory.
+    pub name: String,
+    pub coordinator: CoordinatorConfig,
+    pub l2geth: Option<L2GethConfig>,
+    /// Subdirectory of `keys_dir` holding the prover keys of this network. Defaults to `name`.
+    #[serde(default)]
+    pub keys_subdir: Option<String>,
+    /// Defaults to `prover.supported_proof_types`.
+    #[serde(default)]
+    pub supported_proof_types: Option<Vec<ProofType>>,
+    /// Defaults to `prover.n_workers`.
+    #[serde(default)]
+    pub n_workers: Option<usize>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,9 +132,52 @@ pub struct CoordinatorConfig {
     pub connection_timeout_sec: u64,
 }
 
+/// Connection settings for a remote proving service speaking the REST protocol of
+/// [`HttpProvingService`](crate::prover::HttpProvingService).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProvingServiceConfig {
+    pub base_url: String,
+    pub api_key: String,
+    #[serde(default = "default_proving_service_retry_count")]
+    pub retry_count: u32,
+    #[serde(default = "default_proving_service_retry_wait_time_sec")]
+    pub retry_wait_time_sec: u64,
+    #[serde(default = "default_proving_service_connection_timeout_sec")]
+    pub connection_timeout_sec: u64,
+    /// Uploads inputs to `/v1/inputs` as a stream before creating tasks, instead of embedding
+    /// them in the create-task request.
+    #[serde(default)]
+    pub upload_inputs: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct L2GethConfig {
     pub endpoint: String,
+    /// Endpoints failed over to when `endpoint` is unhealthy, in order of preference.
+    #[serde(default)]
+    pub fallback_endpoints: Vec<String>,
+    /// Prefer the healthy endpoint reporting the highest block over the configured order.
+    #[serde(default)]
+    pub prefer_highest_block: bool,
+    /// Time after which a request to one endpoint is abandoned and the next one is tried.
+    #[serde(default = "default_l2geth_request_timeout_sec")]
+    pub request_timeout_sec: u64,
+    /// Like `request_timeout_sec`, for block trace and witness requests, which take longer.
+    #[serde(default = "default_l2geth_trace_request_timeout_sec")]
+    pub trace_request_timeout_sec: u64,
+    /// How long an unhealthy endpoint is tried after the healthy ones, before being tried in its
+    /// configured order again to check whether it recovered.
+    #[serde(default = "default_l2geth_unhealthy_cooldown_sec")]
+    pub unhealthy_cooldown_sec: u64,
+    /// Maximum number of block traces fetched at the same time when building a chunk input.
+    #[serde(default = "default_trace_fetch_concurrency")]
+    pub trace_fetch_concurrency: usize,
+    /// How many times a block trace request failing with a transient error is retried. Each retry
+    /// goes through all endpoints again.
+    #[serde(default = "default_l2geth_retry_count")]
+    pub retry_count: u32,
+    #[serde(default = "default_l2geth_retry_wait_time_sec")]
+    pub retry_wait_time_sec: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -38,12 +185,182 @@ pub struct ProverConfig {
     pub circuit_type: CircuitType,
     pub supported_proof_types: Vec<ProofType>,
     pub circuit_version: String,
+    /// Workers per network. With a local proving service, the total across networks must not
+    /// exceed the service's `max_concurrency`.
     #[serde(default = "default_n_workers")]
     pub n_workers: usize,
+    /// How long to wait for in-flight tasks to be submitted after a shutdown signal.
+    #[serde(default = "default_drain_timeout_sec")]
+    pub drain_timeout_sec: u64,
+    /// Number of coordinator tasks each worker fetches and builds inputs for ahead of the one
+    /// being proved. `0` disables pipelining.
+    #[serde(default)]
+    pub prefetch_depth: usize,
+    #[serde(default)]
+    pub polling: PollingConfig,
+    /// Upper bound on the tasks handled at once across all workers and networks. Workers wait
+    /// for a free slot in turn, so that networks get a fair share of the proving service.
+    #[serde(default)]
+    pub max_concurrent_tasks: Option<usize>,
+    /// How many times a task failing with a transient error is proved again on this prover
+    /// before the failure is reported to the coordinator.
+    #[serde(default = "default_max_transient_retries")]
+    pub max_transient_retries: u32,
+    /// How long tasks may take, per proof type. Tasks past a deadline are cancelled on the
+    /// proving service and reported to the coordinator as failed.
+    #[serde(default)]
+    pub deadlines: TaskDeadlinesConfig,
+    /// How proving inputs are built.
+    #[serde(default)]
+    pub input: InputConfig,
+    /// Whether tasks still being proved when the drain timeout elapses are cancelled on the
+    /// proving service. If not, they stay persisted in db and are resumed on restart. Off by
+    /// default, since cancelling throws away the compute already spent on tasks that a remote
+    /// proving service would otherwise finish across the restart.
+    #[serde(default)]
+    pub cancel_on_shutdown: bool,
 }
+
+/// Intervals between coordinator polls and proving-service status polls.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PollingConfig {
+    /// Used between coordinator polls while the coordinator has no task to hand out.
+    pub no_task: PollInterval,
+    /// Used after a failed attempt to fetch or handle a task.
+    pub error: PollInterval,
+    /// Used between `query_task` polls while a task is queued or proving.
+    pub in_progress: PollInterval,
+    /// Lower bound for the status poll interval when the proving service reports the remaining
+    /// time of a task, so that nearly finished tasks are polled faster.
+    pub near_completion_sec: u64,
+    /// Used between attempts to resubmit proofs the coordinator could not be reached for.
+    pub submission_retry: PollInterval,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PollInterval {
+    pub interval_sec: u64,
+    /// Upper bound of the exponential backoff. Without it the interval stays fixed.
+    #[serde(default)]
+    pub max_interval_sec: Option<u64>,
+    #[serde(default = "default_backoff_multiplier")]
+    pub multiplier: f64,
+    /// Random spread applied to every interval, as a fraction of it (e.g. `0.2` for ±20%).
+    #[serde(default)]
+    pub jitter: f64,
+}
+
+impl Default for PollingConfig {
+    fn default() -> Self {
+        Self {
+            no_task: PollInterval {
+                interval_sec: 20,
+                max_interval_sec: Some(120),
+                multiplier: default_backoff_multiplier(),
+                jitter: 0.2,
+            },
+            error: PollInterval {
+                interval_sec: 20,
+                max_interval_sec: Some(300),
+                multiplier: default_backoff_multiplier(),
+                jitter: 0.2,
+            },
+            in_progress: PollInterval {
+                interval_sec: 20,
+                max_interval_sec: None,
+                multiplier: default_backoff_multiplier(),
+                jitter: 0.0,
+            },
+            near_completion_sec: 2,
+            submission_retry: PollInterval {
+                interval_sec: 30,
+                max_interval_sec: Some(600),
+                multiplier: default_backoff_multiplier(),
+                jitter: 0.2,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct InputConfig {
+    /// Only saves bandwidth with [`ProvingServiceConfig::upload_inputs`], as inputs embedded in
+    /// requests are sent decompressed.
+    pub compression: InputCompression,
+    /// Inputs larger than this, after compression, are spooled to a temp file instead of being
+    /// kept in memory.
+    pub spool_threshold_bytes: u64,
+    /// Directory of the spooled inputs. Defaults to the system temp directory.
+    pub spool_dir: Option<String>,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            compression: InputCompression::None,
+            spool_threshold_bytes: 16 * 1024 * 1024,
+            spool_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TaskDeadlinesConfig {
+    pub chunk: TaskDeadline,
+    pub batch: TaskDeadline,
+    pub bundle: TaskDeadline,
+}
+
+impl TaskDeadlinesConfig {
+    pub fn for_proof_type(&self, proof_type: ProofType) -> TaskDeadline {
+        match proof_type {
+            ProofType::Chunk => self.chunk.clone(),
+            ProofType::Batch => self.batch.clone(),
+            ProofType::Bundle => self.bundle.clone(),
+            ProofType::Undefined => TaskDeadline::default(),
+        }
+    }
+}
+
+/// Limits on how long a task may take. Unset limits never expire.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TaskDeadline {
+    /// Time a task may stay queued on the proving service after being submitted to it.
+    pub max_queued_sec: Option<u64>,
+    /// Time from fetching a task from the coordinator until it is proved.
+    pub max_total_sec: Option<u64>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DbConfig {}
 
+/// Name of the network served when `Config::networks` is empty.
+pub const DEFAULT_NETWORK_NAME: &str = "default";
+
+fn default_generate_missing_keys() -> bool {
+    true
+}
+
+fn default_keystore_password_env() -> String {
+    "KEYSTORE_PASSWORD".to_string()
+}
+
+fn default_remote_signer_retry_count() -> u32 {
+    3
+}
+
+fn default_remote_signer_retry_wait_time_sec() -> u64 {
+    2
+}
+
+fn default_remote_signer_connection_timeout_sec() -> u64 {
+    10
+}
+
 fn default_health_listener_addr() -> String {
     "0.0.0.0:80".to_string()
 }
@@ -52,6 +369,366 @@ fn default_n_workers() -> usize {
     1
 }
 
+fn default_drain_timeout_sec() -> u64 {
+    60
+}
+
+fn default_trace_fetch_concurrency() -> usize {
+    8
+}
+
+fn default_l2geth_request_timeout_sec() -> u64 {
+    60
+}
+
+fn default_l2geth_trace_request_timeout_sec() -> u64 {
+    600
+}
+
+fn default_l2geth_unhealthy_cooldown_sec() -> u64 {
+    60
+}
+
+fn default_l2geth_retry_count() -> u32 {
+    3
+}
+
+fn default_l2geth_retry_wait_time_sec() -> u64 {
+    2
+}
+
+fn default_max_transient_retries() -> u32 {
+    2
+}
+
+fn default_backoff_multiplier() -> f64 {
+    2.0
+}
+
+fn default_proving_service_retry_count() -> u32 {
+    3
+}
+
+fn default_proving_service_retry_wait_time_sec() -> u64 {
+    5
+}
+
+fn default_proving_service_connection_timeout_sec() -> u64 {
+    60
+}
+
+/// A problem found by [`Config::validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    /// Path of the offending field, e.g. `networks[0].coordinator.base_url`.
+    pub field: String,
+    pub message: String,
+}
+
+/// Every problem found by [`Config::validate`].
+#[derive(Debug, Clone)]
+pub struct ConfigErrors(pub Vec<ConfigError>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid config:")?;
+        for error in &self.0 {
+            write!(f, "\n  {}: {}", error.field, error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+#[derive(Default)]
+struct ConfigErrorsBuilder(Vec<ConfigError>);
+
+impl ConfigErrorsBuilder {
+    fn push(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.push(ConfigError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    fn check_url(&mut self, field: impl Into<String>, url: &str) {
+        if let Err(e) = Url::parse(url) {
+            self.push(field, format!("invalid url {url:?}: {e}"));
+        }
+    }
+
+    fn check_poll_interval(&mut self, field: &str, interval: &PollInterval) {
+        if interval.multiplier < 1.0 {
+            self.push(format!("{field}.multiplier"), "must be at least 1");
+        }
+        if !(0.0..=1.0).contains(&interval.jitter) {
+            self.push(format!("{field}.jitter"), "must be between 0 and 1");
+        }
+        if interval
+            .max_interval_sec
+            .is_some_and(|max| max < interval.interval_sec)
+        {
+            self.push(
+                format!("{field}.max_interval_sec"),
+                "must not be less than interval_sec",
+            );
+        }
+    }
+}
+
+/// Prefix of the env vars overriding any config field. The rest of the name is the path of the
+/// field, with `__` between segments and array indices as segments, e.g.
+/// `SCROLL_PROVER__COORDINATOR__RETRY_COUNT=5` or `SCROLL_PROVER__NETWORKS__0__N_WORKERS=2`.
+/// Missing sections are created. With a `_FILE` suffix, the value is read from the named file,
+/// e.g. `SCROLL_PROVER__REMOTE_SIGNER__BASE_URL_FILE=/run/secrets/signer_url`, unless the config
+/// has a field of that exact name such as `keystore.password_file`.
+pub const ENV_OVERRIDE_PREFIX: &str = "SCROLL_PROVER__";
+
+/// An `ENV_OVERRIDE_PREFIX` env var, with the path of the field it sets.
+struct EnvOverride {
+    var: String,
+    path: Vec<String>,
+    val: String,
+}
+
+/// Collects the `ENV_OVERRIDE_PREFIX` overrides among `vars`, sorted by path so that they apply
+/// in a stable order, with array indices compared as numbers so that `__2__` comes before
+/// `__10__`.
+fn env_overrides(vars: impl IntoIterator<Item = (String, String)>) -> Result<Vec<EnvOverride>> {
+    let mut overrides = vec![];
+    for (var, val) in vars {
+        let Some(name) = var.strip_prefix(ENV_OVERRIDE_PREFIX) else {
+            continue;
+        };
+        let path: Vec<String> = name.split("__").map(str::to_lowercase).collect();
+        if path.iter().any(String::is_empty) {
+            return Err(anyhow!("{var} is not a valid config field path"));
+        }
+        overrides.push(EnvOverride { var, path, val });
+    }
+    overrides.sort_by_cached_key(|o| {
+        o.path
+            .iter()
+            .map(|segment| (segment.parse::<usize>().ok(), segment.clone()))
+            .collect::<Vec<_>>()
+    });
+    Ok(overrides)
+}
+
+/// Config fields named with a `_file` suffix, which overrides set as is even where their section
+/// is missing.
+const FILE_NAME_FIELDS: &[&str] = &["password_file"];
+
+/// Applies `overrides` to `value` and deserializes the result.
+///
+/// A field without a current value takes its override as a string, unless it looks like a JSON
+/// object or array. Only if the config then fails to deserialize because such a field expects a
+/// number or a bool, e.g. an unset `Option<u64>`, is the override parsed as one instead.
+fn apply_env_overrides(mut value: Value, overrides: Vec<EnvOverride>) -> Result<Config> {
+    let mut untyped = vec![];
+    for env_override in overrides {
+        let var = env_override.var.clone();
+        if let Some((pointer, val)) = apply_env_override(&mut value, env_override)? {
+            untyped.push((var, pointer, val));
+        }
+    }
+    loop {
+        let error = match serde_json::from_value(value.clone()) {
+            Ok(cfg) => return Ok(cfg),
+            Err(e) => e.to_string(),
+        };
+        let Some(i) = untyped
+            .iter()
+            .position(|(_, _, val)| error.starts_with(&format!("invalid type: string {val:?}")))
+        else {
+            return Err(anyhow!(
+                "invalid {ENV_OVERRIDE_PREFIX}* env var override: {error}"
+            ));
+        };
+        let (var, pointer, val) = untyped.swap_remove(i);
+        let scalar = serde_json::from_str::<Value>(&val)
+            .ok()
+            .filter(|scalar| scalar.is_number() || scalar.is_boolean())
+            .ok_or_else(|| anyhow!("{var}: {error}"))?;
+        if let Some(field) = value.pointer_mut(&pointer) {
+            *field = scalar;
+        }
+    }
+}
+
+/// Applies one override, reading the value from a file for a `_file` path that is not a field.
+/// Returns the pointer and value of the field if it had no current value to type the override
+/// against, so that it was set as a string.
+fn apply_env_override(
+    value: &mut Value,
+    env_override: EnvOverride,
+) -> Result<Option<(String, String)>> {
+    let EnvOverride { var, mut path, val } = env_override;
+    let pointer = format!("/{}", path.join("/"));
+    let file_field = path
+        .last()
+        .filter(|segment| !FILE_NAME_FIELDS.contains(&segment.as_str()))
+        .and_then(|segment| segment.strip_suffix("_file"))
+        .filter(|field| !field.is_empty())
+        .map(str::to_string);
+    let val = match file_field {
+        Some(field) if value.pointer(&pointer).is_none() => {
+            let content = std::fs::read_to_string(&val)
+                .map_err(|e| anyhow!("{var}: failed to read {val} for {pointer}: {e}"))?;
+            *path.last_mut().unwrap() = field;
+            content.trim_end_matches(['\r', '\n']).to_string()
+        }
+        _ => val,
+    };
+    let pointer = format!("/{}", path.join("/"));
+    let untyped = value.pointer(&pointer).is_none_or(Value::is_null);
+    set_config_path(value, &path, val.clone()).map_err(|e| anyhow!("{var}: {e}"))?;
+    Ok(
+        (untyped && value.pointer(&pointer).is_some_and(Value::is_string))
+            .then_some((pointer, val)),
+    )
+}
+
+/// Sets the field at `path` to `val`, creating missing sections along the way. `val` stays a
+/// string where the field already holds one or has no value, unless it is then a JSON object or
+/// array. Otherwise it is parsed as JSON, and must be of the same type as the field's current
+/// value.
+fn set_config_path(value: &mut Value, path: &[String], val: String) -> Result<()> {
+    let Some((segment, rest)) = path.split_first() else {
+        *value = match &*value {
+            Value::String(_) => Value::String(val),
+            Value::Null => serde_json::from_str(&val)
+                .ok()
+                .filter(|parsed: &Value| parsed.is_object() || parsed.is_array())
+                .unwrap_or(Value::String(val)),
+            current => {
+                let parsed = serde_json::from_str::<Value>(&val).ok().filter(|parsed| {
+                    match (current, parsed) {
+                        // Integer fields only take integers, e.g. not `1e3`.
+                        (Value::Number(current), Value::Number(parsed)) => {
+                            current.is_f64() || !parsed.is_f64()
+                        }
+                        (Value::Bool(_), Value::Bool(_))
+                        | (Value::Array(_), Value::Array(_))
+                        | (Value::Object(_), Value::Object(_)) => true,
+                        _ => false,
+                    }
+                });
+                parsed.ok_or_else(|| {
+                    anyhow!(
+                        "{val:?} is not a valid {} like the current value {current}",
+                        json_type_name(current)
+                    )
+                })?
+            }
+        };
+        return Ok(());
+    };
+
+    if value.is_null() {
+        *value = Value::Object(Default::default());
+    }
+    let child = match value {
+        Value::Object(fields) => fields.entry(segment.clone()).or_insert(Value::Null),
+        Value::Array(items) => {
+            let index: usize = segment
+                .parse()
+                .map_err(|_| anyhow!("{segment} is not an array index"))?;
+            if index > items.len() {
+                return Err(anyhow!("array index {index} is out of bounds"));
+            }
+            if index == items.len() {
+                items.push(Value::Null);
+            }
+            &mut items[index]
+        }
+        _ => return Err(anyhow!("{segment} is not a config section")),
+    };
+    set_config_path(child, rest, val)
+}
+
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Config file format, detected from the file extension. Files without a known extension are
+/// read as JSON.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::Toml,
+            Some("yaml" | "yml") => Self::Yaml,
+            _ => Self::Json,
+        }
+    }
+
+    pub fn parse(self, content: &str) -> Result<Value> {
+        match self {
+            Self::Json => serde_json::from_str(content).map_err(|e| anyhow!(e)),
+            Self::Toml => toml::from_str(content).map_err(|e| anyhow!(e)),
+            Self::Yaml => serde_yaml::from_str(content).map_err(|e| anyhow!(e)),
+        }
+    }
+}
+
+/// Reads a config file of any supported format as a JSON value.
+pub fn read_config_value(path: impl AsRef<Path>) -> Result<Value> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("failed to read config file {}: {e}", path.display()))?;
+    ConfigFormat::from_path(path)
+        .parse(&content)
+        .map_err(|e| anyhow!("failed to parse config file {}: {e}", path.display()))
+}
+
+/// Merges `overlay` into `base`: objects are merged key by key, anything else in `overlay`
+/// replaces its counterpart in `base`.
+pub fn merge_config_values(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_config_values(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Loads a config of type `T` from `base_file`, with `overlay_file` merged on top if given.
+/// Backends embedding [`Config`] in their own config type can use this to get the same layering,
+/// then call [`Config::override_with_env`] on the embedded config.
+pub fn load_layered_config<T: DeserializeOwned>(
+    base_file: impl AsRef<Path>,
+    overlay_file: Option<impl AsRef<Path>>,
+) -> Result<T> {
+    let mut value = read_config_value(base_file)?;
+    if let Some(overlay_file) = overlay_file {
+        merge_config_values(&mut value, read_config_value(overlay_file)?);
+    }
+    serde_json::from_value(value).map_err(|e| anyhow!(e))
+}
+
 impl Config {
     pub fn from_reader<R>(reader: R) -> Result<Self>
     where
@@ -60,13 +737,21 @@ impl Config {
         serde_json::from_reader(reader).map_err(|e| anyhow!(e))
     }
 
+    /// Reads a JSON, TOML or YAML config file, depending on its extension.
     pub fn from_file(file_name: String) -> Result<Self> {
-        let file = File::open(file_name)?;
-        Config::from_reader(&file)
+        load_layered_config(file_name, None::<&str>)
     }
 
     pub fn from_file_and_env(file_name: String) -> Result<Self> {
-        let mut cfg = Config::from_file(file_name)?;
+        Self::from_layered_files_and_env(file_name, None)
+    }
+
+    /// Reads `base_file`, merges `overlay_file` on top if given, then applies env var overrides.
+    pub fn from_layered_files_and_env(
+        base_file: String,
+        overlay_file: Option<String>,
+    ) -> Result<Self> {
+        let mut cfg: Self = load_layered_config(base_file, overlay_file)?;
         cfg.override_with_env()?;
         Ok(cfg)
     }
@@ -81,7 +766,8 @@ impl Config {
             .transpose()
     }
 
-    fn override_with_env(&mut self) -> Result<()> {
+    /// Applies the env var overrides, reading a `.env` file first if there is one.
+    pub fn override_with_env(&mut self) -> Result<()> {
         dotenv().ok();
 
         if let Some(val) = Self::get_env_var("PROVER_NAME_PREFIX")? {
@@ -91,11 +777,34 @@ impl Config {
             self.keys_dir = val;
         }
         if let Some(val) = Self::get_env_var("COORDINATOR_BASE_URL")? {
-            self.coordinator.base_url = val;
+            match &mut self.coordinator {
+                Some(coordinator) => coordinator.base_url = val,
+                // Each network has its own coordinator, so there is no single one to point at.
+                None => {
+                    return Err(ConfigErrors(vec![ConfigError {
+                        field: "coordinator.base_url".to_string(),
+                        message: format!(
+                            "COORDINATOR_BASE_URL is set but coordinator is not configured, use \
+                             {ENV_OVERRIDE_PREFIX}NETWORKS__<i>__COORDINATOR__BASE_URL instead"
+                        ),
+                    }])
+                    .into())
+                }
+            }
         }
         if let Some(val) = Self::get_env_var("L2GETH_ENDPOINT")? {
+            match &mut self.l2geth {
+                Some(l2geth) => l2geth.endpoint = val,
+                None => self.l2geth = Some(serde_json::from_value(json!({ "endpoint": val }))?),
+            }
+        }
+        if let Some(val) = Self::get_env_var("L2GETH_FALLBACK_ENDPOINTS")? {
             if let Some(l2geth) = &mut self.l2geth {
-                l2geth.endpoint = val;
+                l2geth.fallback_endpoints = val
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
             }
         }
 
@@ -108,13 +817,13 @@ impl Config {
 
             self.prover.supported_proof_types = values_vec
                 .iter()
-                .map(|value| match value.parse::<u8>() {
-                    Ok(num) => ProofType::from_u8(num),
-                    Err(e) => {
-                        panic!("Failed to parse circuit type: {}", e);
-                    }
+                .map(|value| {
+                    value
+                        .parse::<u8>()
+                        .map(ProofType::from_u8)
+                        .map_err(|e| anyhow!("failed to parse PROOF_TYPES entry {value:?}: {e}"))
                 })
-                .collect::<Vec<ProofType>>();
+                .collect::<Result<Vec<ProofType>>>()?;
         }
 
         if let Some(val) = Self::get_env_var("N_WORKERS")? {
@@ -125,28 +834,206 @@ impl Config {
             self.db_path = Option::from(val);
         }
 
+        let overrides =
+            env_overrides(std::env::vars_os().filter_map(|(key, val)| {
+                Some((key.into_string().ok()?, val.into_string().ok()?))
+            }))?;
+        if !overrides.is_empty() {
+            *self = apply_env_overrides(serde_json::to_value(&*self)?, overrides)?;
+        }
+
         Ok(())
     }
 
+    /// Returns the networks to serve, with the defaults from `prover` and `keys_dir` applied.
+    pub fn networks(&self) -> Result<Vec<NetworkConfig>> {
+        if self.networks.is_empty() {
+            let coordinator = self
+                .coordinator
+                .clone()
+                .ok_or_else(|| anyhow!("either coordinator or networks must be configured"))?;
+            return Ok(vec![NetworkConfig {
+                name: DEFAULT_NETWORK_NAME.to_string(),
+                coordinator,
+                l2geth: self.l2geth.clone(),
+                keys_subdir: Some(String::new()),
+                supported_proof_types: Some(self.prover.supported_proof_types.clone()),
+                n_workers: Some(self.prover.n_workers),
+            }]);
+        }
+
+        Ok(self
+            .networks
+            .iter()
+            .cloned()
+            .map(|mut network| {
+                network
+                    .keys_subdir
+                    .get_or_insert_with(|| network.name.clone());
+                network
+                    .supported_proof_types
+                    .get_or_insert_with(|| self.prover.supported_proof_types.clone());
+                network.n_workers.get_or_insert(self.prover.n_workers);
+                network
+            })
+            .collect())
+    }
+
+    /// Checks the whole config, reporting every problem found rather than only the first.
+    pub fn validate(&self) -> std::result::Result<(), ConfigErrors> {
+        let mut errors = ConfigErrorsBuilder::default();
+
+        if self.db_path.is_none() {
+            errors.push("db_path", "is required");
+        }
+        if let Err(e) = self.health_listener_addr.parse::<SocketAddr>() {
+            errors.push(
+                "health_listener_addr",
+                format!("invalid socket address: {e}"),
+            );
+        }
+        match self.prover.circuit_type {
+            CircuitType::Undefined => errors.push("prover.circuit_type", "unknown circuit type"),
+            CircuitType::OpenVM if !cfg!(feature = "openvm") => errors.push(
+                "prover.circuit_type",
+                "OpenVM requires building with the openvm feature",
+            ),
+            _ => {}
+        }
+        if self.prover.max_concurrent_tasks == Some(0) {
+            errors.push("prover.max_concurrent_tasks", "must be at least 1");
+        }
+        for (name, deadline) in [
+            ("chunk", &self.prover.deadlines.chunk),
+            ("batch", &self.prover.deadlines.batch),
+            ("bundle", &self.prover.deadlines.bundle),
+        ] {
+            if deadline.max_queued_sec == Some(0) {
+                errors.push(
+                    format!("prover.deadlines.{name}.max_queued_sec"),
+                    "must be at least 1",
+                );
+            }
+            if deadline.max_total_sec == Some(0) {
+                errors.push(
+                    format!("prover.deadlines.{name}.max_total_sec"),
+                    "must be at least 1",
+                );
+            }
+        }
+        let polling = &self.prover.polling;
+        errors.check_poll_interval("prover.polling.no_task", &polling.no_task);
+        errors.check_poll_interval("prover.polling.error", &polling.error);
+        errors.check_poll_interval("prover.polling.in_progress", &polling.in_progress);
+        errors.check_poll_interval("prover.polling.submission_retry", &polling.submission_retry);
+        if self.block_cache.enabled && self.block_cache.eviction_interval_sec == 0 {
+            errors.push("block_cache.eviction_interval_sec", "must be at least 1");
+        }
+
+        let mut total_workers = 0;
+        if self.networks.is_empty() && self.coordinator.is_none() {
+            errors.push(
+                "coordinator",
+                "either coordinator or networks must be configured",
+            );
+        }
+        let networks = self.networks().unwrap_or_default();
+        for (i, network) in networks.iter().enumerate() {
+            // The implicit network is made of top-level fields.
+            let (prefix, proof_types_field, n_workers_field) = if self.networks.is_empty() {
+                (
+                    String::new(),
+                    "prover.supported_proof_types".to_string(),
+                    "prover.n_workers".to_string(),
+                )
+            } else {
+                let prefix = format!("networks[{i}].");
+                (
+                    prefix.clone(),
+                    format!("{prefix}supported_proof_types"),
+                    format!("{prefix}n_workers"),
+                )
+            };
+
+            if network.name.is_empty() {
+                errors.push(format!("{prefix}name"), "must not be empty");
+            } else if networks[..i].iter().any(|n| n.name == network.name) {
+                errors.push(
+                    format!("{prefix}name"),
+                    format!("duplicate network name {:?}", network.name),
+                );
+            }
+            errors.check_url(
+                format!("{prefix}coordinator.base_url"),
+                &network.coordinator.base_url,
+            );
+            if let Some(l2geth) = &network.l2geth {
+                errors.check_url(format!("{prefix}l2geth.endpoint"), &l2geth.endpoint);
+                for (j, endpoint) in l2geth.fallback_endpoints.iter().enumerate() {
+                    errors.check_url(format!("{prefix}l2geth.fallback_endpoints[{j}]"), endpoint);
+                }
+            }
+
+            let proof_types = network.supported_proof_types.as_deref().unwrap_or_default();
+            if proof_types.is_empty() {
+                errors.push(&proof_types_field, "must not be empty");
+            }
+            if proof_types.contains(&ProofType::Undefined) {
+                errors.push(&proof_types_field, "unknown proof type");
+            }
+            if proof_types.contains(&ProofType::Chunk) && network.l2geth.is_none() {
+                errors.push(
+                    format!("{prefix}l2geth"),
+                    "is required to prove chunk tasks",
+                );
+            }
+
+            let n_workers = network.n_workers.unwrap_or_default();
+            if n_workers == 0 {
+                errors.push(&n_workers_field, "must be at least 1");
+            }
+            total_workers += n_workers;
+        }
+
+        if let Some(remote_signer) = &self.remote_signer {
+            errors.check_url("remote_signer.base_url", &remote_signer.base_url);
+            if remote_signer.public_keys.len() < total_workers {
+                errors.push(
+                    "remote_signer.public_keys",
+                    format!(
+                        "{} public keys configured for {total_workers} workers",
+                        remote_signer.public_keys.len()
+                    ),
+                );
+            }
+        } else if self.keys_dir.is_empty() {
+            errors.push("keys_dir", "must not be empty");
+        }
+
+        if errors.0.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigErrors(errors.0))
+        }
+    }
+
     pub fn coordinator_prover_type(&self) -> Vec<ProverType> {
+        self.prover_types_for(&self.prover.supported_proof_types)
+    }
+
+    pub fn prover_types_for(&self, proof_types: &[ProofType]) -> Vec<ProverType> {
         if self.prover.circuit_type == CircuitType::OpenVM {
             vec![ProverType::OpenVM]
         } else {
             let mut prover_types = vec![];
-            if self
-                .prover
-                .supported_proof_types
+            if proof_types
                 .iter()
                 .any(|t| *t == ProofType::Bundle || *t == ProofType::Batch)
             {
                 prover_types.push(ProverType::Batch)
             }
 
-            if self
-                .prover
-                .supported_proof_types
-                .contains(&ProofType::Chunk)
-            {
+            if proof_types.contains(&ProofType::Chunk) {
                 prover_types.push(ProverType::Chunk)
             }
 
@@ -154,3 +1041,179 @@ impl Config {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layered_config_files() {
+        let dir = std::env::temp_dir().join(format!("config_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let base = dir.join("base.toml");
+        std::fs::write(
+            &base,
+            r#"
+prover_name_prefix = "prover"
+keys_dir = "keys"
+db_path = "db"
+
+[coordinator]
+base_url = "http://localhost:8555"
+retry_count = 3
+retry_wait_time_sec = 5
+connection_timeout_sec = 60
+
+[prover]
+circuit_type = 1
+supported_proof_types = [2, 3]
+circuit_version = "v0.13.1"
+n_workers = 2
+"#,
+        )
+        .unwrap();
+        let overlay = dir.join("staging.yaml");
+        std::fs::write(
+            &overlay,
+            "coordinator:\n  base_url: http://staging:8555\nprover:\n  n_workers: 4\n",
+        )
+        .unwrap();
+
+        let cfg = Config::from_file(base.to_str().unwrap().to_string()).unwrap();
+        assert_eq!(cfg.prover.n_workers, 2);
+
+        let cfg: Config = load_layered_config(&base, Some(&overlay)).unwrap();
+        let coordinator = cfg.coordinator.unwrap();
+        assert_eq!(coordinator.base_url, "http://staging:8555");
+        assert_eq!(coordinator.retry_count, 3);
+        assert_eq!(cfg.prover.n_workers, 4);
+        assert_eq!(
+            cfg.prover.supported_proof_types,
+            vec![ProofType::Batch, ProofType::Bundle]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let dir = std::env::temp_dir().join(format!("config_env_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let secret = dir.join("base_url");
+        std::fs::write(&secret, "http://secret:8555\n").unwrap();
+
+        let cfg = Config::from_file("conf/config.json".to_string()).unwrap();
+        let mut value = serde_json::to_value(&cfg).unwrap();
+        let vars = [
+            ("SCROLL_PROVER__COORDINATOR__RETRY_COUNT", "5".to_string()),
+            ("SCROLL_PROVER__PROVER__CIRCUIT_VERSION", "1".to_string()),
+            (
+                "SCROLL_PROVER__COORDINATOR__BASE_URL_FILE",
+                secret.to_str().unwrap().to_string(),
+            ),
+            ("SCROLL_PROVER__BLOCK_CACHE__MAX_AGE_SEC", "60".to_string()),
+            ("SCROLL_PROVER__NETWORKS__0__NAME", "mainnet".to_string()),
+            (
+                "SCROLL_PROVER__KEYSTORE__PASSWORD_FILE",
+                "/run/secrets/password".to_string(),
+            ),
+            ("UNRELATED", "1".to_string()),
+        ];
+        let overrides = env_overrides(vars.map(|(key, val)| (key.to_string(), val))).unwrap();
+        assert_eq!(overrides.len(), 6);
+        for env_override in overrides {
+            apply_env_override(&mut value, env_override).unwrap();
+        }
+
+        assert_eq!(value["coordinator"]["retry_count"], json!(5));
+        assert_eq!(
+            value["coordinator"]["base_url"],
+            json!("http://secret:8555")
+        );
+        assert_eq!(value["prover"]["circuit_version"], json!("1"));
+        assert_eq!(value["block_cache"]["max_age_sec"], json!(60));
+        assert_eq!(value["networks"][0]["name"], json!("mainnet"));
+        assert_eq!(
+            value["keystore"]["password_file"],
+            json!("/run/secrets/password")
+        );
+
+        assert!(set_config_path(
+            &mut value,
+            &["keys_dir".to_string(), "x".to_string()],
+            "1".to_string()
+        )
+        .is_err());
+
+        // Values of another type than the field are rejected, naming the env var.
+        for val in ["1e3", "null", "five"] {
+            let overrides = env_overrides([(
+                "SCROLL_PROVER__COORDINATOR__RETRY_COUNT".to_string(),
+                val.to_string(),
+            )])
+            .unwrap();
+            let error = apply_env_override(&mut value, overrides.into_iter().next().unwrap())
+                .unwrap_err()
+                .to_string();
+            assert!(error.contains("SCROLL_PROVER__COORDINATOR__RETRY_COUNT"));
+        }
+        assert_eq!(value["coordinator"]["retry_count"], json!(5));
+
+        // Unset fields take the override as a string, or as a number if that is what they hold.
+        let mut cfg = Config::from_file("conf/config.json".to_string()).unwrap();
+        cfg.db_path = None;
+        let overrides = env_overrides(
+            [
+                ("SCROLL_PROVER__DB_PATH", "123"),
+                ("SCROLL_PROVER__PROVER__MAX_CONCURRENT_TASKS", "4"),
+                (
+                    "SCROLL_PROVER__PROVER__DEADLINES__BATCH__MAX_TOTAL_SEC",
+                    "60",
+                ),
+            ]
+            .map(|(key, val)| (key.to_string(), val.to_string())),
+        )
+        .unwrap();
+        let cfg = apply_env_overrides(serde_json::to_value(&cfg).unwrap(), overrides).unwrap();
+        assert_eq!(cfg.db_path.as_deref(), Some("123"));
+        assert_eq!(cfg.prover.max_concurrent_tasks, Some(4));
+        assert_eq!(cfg.prover.deadlines.batch.max_total_sec, Some(60));
+
+        // Array indices apply in numeric order.
+        let paths: Vec<String> = env_overrides(
+            ["10", "2", "0"]
+                .map(|i| (format!("SCROLL_PROVER__NETWORKS__{i}__NAME"), String::new())),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|o| o.path[1].clone())
+        .collect();
+        assert_eq!(paths, vec!["0", "2", "10"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let mut cfg = Config::from_file("conf/config.json".to_string()).unwrap();
+        cfg.prover.circuit_type = CircuitType::Halo2;
+        assert!(cfg.validate().is_ok());
+
+        cfg.db_path = None;
+        cfg.prover.circuit_type = CircuitType::Undefined;
+        cfg.prover.supported_proof_types = vec![ProofType::Chunk, ProofType::Undefined];
+        cfg.l2geth = None;
+        cfg.coordinator.as_mut().unwrap().base_url = "not a url".to_string();
+        let errors = cfg.validate().unwrap_err();
+        let fields: Vec<&str> = errors.0.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "db_path",
+                "prover.circuit_type",
+                "coordinator.base_url",
+                "prover.supported_proof_types",
+                "l2geth",
+            ]
+        );
+    }
+}
@@ -2,12 +2,13 @@ use super::{
     ChallengeResponseData, GetTaskRequest, GetTaskResponseData, LoginRequest, LoginResponseData,
     Response, SubmitProofRequest, SubmitProofResponseData,
 };
-use crate::config::CoordinatorConfig;
+use crate::{config::CoordinatorConfig, metrics};
 use core::time::Duration;
 use reqwest::{header::CONTENT_TYPE, Url};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use serde::Serialize;
+use std::time::Instant;
 
 pub struct Api {
     pub base_url: Url,
@@ -42,7 +43,23 @@ impl Api {
         method: &str,
         req: &Req,
         token: &String,
-    ) -> anyhow::Result<Resp>
+    ) -> anyhow::Result<Response<Resp>>
+    where
+        Req: ?Sized + Serialize,
+        Resp: serde::de::DeserializeOwned,
+    {
+        let started = Instant::now();
+        let response = self.do_post_with_token(method, req, token).await;
+        metrics::observe_coordinator_request(method, started, &response);
+        response
+    }
+
+    async fn do_post_with_token<Req, Resp>(
+        &self,
+        method: &str,
+        req: &Req,
+        token: &String,
+    ) -> anyhow::Result<Response<Resp>>
     where
         Req: ?Sized + Serialize,
         Resp: serde::de::DeserializeOwned,
@@ -79,6 +96,13 @@ impl Api {
 
     pub async fn challenge(&self) -> anyhow::Result<Response<ChallengeResponseData>> {
         let method = "/coordinator/v1/challenge";
+        let started = Instant::now();
+        let response = self.do_challenge(method).await;
+        metrics::observe_coordinator_request(method, started, &response);
+        response
+    }
+
+    async fn do_challenge(&self, method: &str) -> anyhow::Result<Response<ChallengeResponseData>> {
         let url = self.build_url(method)?;
 
         let response = self
@@ -1,8 +1,9 @@
 use super::{
-    api::Api, error::ErrorCode, GetTaskRequest, GetTaskResponseData, KeySigner, LoginMessage,
-    LoginRequest, ProverType, Response, SubmitProofRequest, SubmitProofResponseData,
+    api::Api, error::ErrorCode, GetTaskRequest, GetTaskResponseData, LoginMessage, LoginRequest,
+    ProverType, Response, Signer, SubmitProofRequest, SubmitProofResponseData,
 };
 use crate::{config::CoordinatorConfig, prover::ProverProviderType, utils::get_version};
+use std::sync::{Arc, Mutex as StdMutex};
 use tokio::sync::{Mutex, MutexGuard};
 
 pub struct CoordinatorClient {
@@ -10,9 +11,10 @@ pub struct CoordinatorClient {
     vks: Vec<String>,
     pub prover_name: String,
     pub prover_provider_type: ProverProviderType,
-    pub key_signer: KeySigner,
+    pub key_signer: Arc<dyn Signer>,
     api: Api,
     token: Mutex<Option<String>>,
+    last_login_result: StdMutex<Option<Result<(), String>>>,
 }
 
 impl CoordinatorClient {
@@ -22,7 +24,7 @@ impl CoordinatorClient {
         vks: Vec<String>,
         prover_name: String,
         prover_provider_type: ProverProviderType,
-        key_signer: KeySigner,
+        key_signer: Arc<dyn Signer>,
     ) -> anyhow::Result<Self> {
         let api = Api::new(cfg)?;
         let client = Self {
@@ -33,6 +35,7 @@ impl CoordinatorClient {
             key_signer,
             api,
             token: Mutex::new(None),
+            last_login_result: StdMutex::new(None),
         };
         Ok(client)
     }
@@ -81,7 +84,15 @@ impl CoordinatorClient {
             _ => (),
         }
 
-        self.login(token_guard).await
+        let result = self.login(token_guard).await;
+        *self.last_login_result.lock().unwrap() =
+            Some(result.as_ref().map(|_| ()).map_err(|e| e.to_string()));
+        result
+    }
+
+    /// Returns the outcome of the most recent login attempt, or `None` if none was made yet.
+    pub fn last_login_result(&self) -> Option<Result<(), String>> {
+        self.last_login_result.lock().unwrap().clone()
     }
 
     async fn login<'t>(
@@ -120,6 +131,7 @@ impl CoordinatorClient {
         let signature = self
             .key_signer
             .sign_buffer(&buffer)
+            .await
             .map_err(|e| anyhow::anyhow!("Failed to sign the login message: {e}"))?;
 
         let login_request = LoginRequest {
@@ -1,4 +1,4 @@
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -41,6 +41,32 @@ impl ErrorCode {
             }
         }
     }
+
+    pub fn to_i32(self) -> i32 {
+        match self {
+            ErrorCode::Success => 0,
+            ErrorCode::InternalServerError => 500,
+            ErrorCode::ErrProverStatsAPIParameterInvalidNo => 10001,
+            ErrorCode::ErrProverStatsAPIProverTaskFailure => 10002,
+            ErrorCode::ErrProverStatsAPIProverTotalRewardFailure => 10003,
+            ErrorCode::ErrCoordinatorParameterInvalidNo => 20001,
+            ErrorCode::ErrCoordinatorGetTaskFailure => 20002,
+            ErrorCode::ErrCoordinatorHandleZkProofFailure => 20003,
+            ErrorCode::ErrCoordinatorEmptyProofData => 20004,
+            ErrorCode::ErrJWTCommonErr => 50000,
+            ErrorCode::ErrJWTTokenExpired => 50001,
+            ErrorCode::Undefined(v) => v,
+        }
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_i32(self.to_i32())
+    }
 }
 
 impl<'de> Deserialize<'de> for ErrorCode {
@@ -12,27 +12,86 @@ use rand;
 use rand::RngCore;
 use std::fs::File;
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tiny_keccak::{Hasher, Keccak};
 
 const DEFAULT_KEY_SIZE: usize = 32usize;
 
-fn read_key_from_disk(key_path: &PathBuf) -> anyhow::Result<Vec<u8>> {
+/// How prover keys are stored on disk.
+#[derive(Clone)]
+pub enum KeyStorage {
+    /// The secret as plain hex.
+    Plaintext,
+    /// An Ethereum JSON keystore (scrypt or pbkdf2, AES-128-CTR) unlocked with `password`.
+    Keystore { password: String },
+}
+
+fn read_key_from_disk(key_path: &Path, storage: &KeyStorage) -> anyhow::Result<Vec<u8>> {
     let mut file = File::open(key_path)?;
     let mut content = String::new();
     file.read_to_string(&mut content)?;
-    Ok(hex::decode(content)?)
+    let is_keystore = content.trim_start().starts_with('{');
+
+    match storage {
+        KeyStorage::Plaintext if is_keystore => anyhow::bail!(
+            "key {} is an encrypted keystore but no keystore is configured",
+            key_path.display()
+        ),
+        KeyStorage::Plaintext => Ok(hex::decode(content.trim())?),
+        KeyStorage::Keystore { password } if is_keystore => {
+            eth_keystore::decrypt_key(key_path, password).map_err(|e| {
+                anyhow::anyhow!("failed to decrypt keystore {}: {e}", key_path.display())
+            })
+        }
+        KeyStorage::Keystore { password } => {
+            let secret = hex::decode(content.trim())?;
+            log::warn!(
+                "encrypting plaintext key {} into a keystore",
+                key_path.display()
+            );
+            save_keystore(key_path, &secret, password)?;
+            Ok(secret)
+        }
+    }
 }
 
-fn gen_key_save_to_disk(key_path: &PathBuf) -> anyhow::Result<Vec<u8>> {
+/// Writes `secret` as a keystore at `key_path`, replacing any existing file atomically.
+fn save_keystore(key_path: &Path, secret: &[u8], password: &str) -> anyhow::Result<()> {
+    let dir = key_path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("invalid key path {}", key_path.display()))?;
+    let file_name = key_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow::anyhow!("invalid key path {}", key_path.display()))?;
+    let tmp_name = format!("{file_name}.tmp");
+
+    eth_keystore::encrypt_key(
+        dir,
+        &mut rand::thread_rng(),
+        secret,
+        password,
+        Some(&tmp_name),
+    )
+    .map_err(|e| anyhow::anyhow!("failed to encrypt key {}: {e}", key_path.display()))?;
+    std::fs::rename(dir.join(tmp_name), key_path)?;
+    Ok(())
+}
+
+fn gen_key_save_to_disk(key_path: &Path, storage: &KeyStorage) -> anyhow::Result<Vec<u8>> {
     // Generate a random private key.
     let mut secret = vec![0u8; DEFAULT_KEY_SIZE];
     let mut rng = rand::thread_rng();
     rng.fill_bytes(secret.as_mut_slice());
 
-    let content = hex::encode(secret.clone());
-    let mut file = File::create(key_path)?;
-    file.write_all(content.as_bytes())?;
+    match storage {
+        KeyStorage::Plaintext => {
+            let content = hex::encode(secret.clone());
+            let mut file = File::create(key_path)?;
+            file.write_all(content.as_bytes())?;
+        }
+        KeyStorage::Keystore { password } => save_keystore(key_path, &secret, password)?,
+    }
 
     Ok(secret)
 }
@@ -45,12 +104,32 @@ pub struct KeySigner {
 
 impl KeySigner {
     pub fn new(key_path: &PathBuf) -> anyhow::Result<Self> {
-        let secret = match read_key_from_disk(key_path) {
-            Ok(secret) => secret,
-            Err(_) => gen_key_save_to_disk(key_path)?,
+        Self::load(key_path, &KeyStorage::Plaintext, true)
+    }
+
+    /// Loads the key at `key_path`, generating it if it is missing and `generate_missing` is set.
+    /// With [`KeyStorage::Keystore`], a plaintext key found at `key_path` is encrypted in place.
+    pub fn load(
+        key_path: &Path,
+        storage: &KeyStorage,
+        generate_missing: bool,
+    ) -> anyhow::Result<Self> {
+        let secret = if key_path.exists() {
+            read_key_from_disk(key_path, storage)?
+        } else if generate_missing {
+            gen_key_save_to_disk(key_path, storage)?
+        } else {
+            anyhow::bail!(
+                "key {} is missing and generating keys is disabled",
+                key_path.display()
+            );
         };
 
-        let secret_key = SecretKey::from_bytes(secret.as_slice().into())?;
+        Self::from_secret(&secret)
+    }
+
+    fn from_secret(secret: &[u8]) -> anyhow::Result<Self> {
+        let secret_key = SecretKey::from_bytes(secret.into())?;
         let signing_key = SigningKey::from(secret_key.clone());
         Ok(Self {
             public_key: secret_key.public_key(),
@@ -60,12 +139,7 @@ impl KeySigner {
 
     pub fn new_from_secret_key(secret_key: &str) -> anyhow::Result<Self> {
         let secret = hex::decode(secret_key).unwrap();
-        let secret_key = SecretKey::from_bytes(secret.as_slice().into())?;
-        let signing_key = SigningKey::from(secret_key.clone());
-        Ok(Self {
-            public_key: secret_key.public_key(),
-            signing_key,
-        })
+        Self::from_secret(&secret)
     }
 
     pub fn get_public_key(&self) -> String {
@@ -124,3 +198,45 @@ pub fn keccak256<T: AsRef<[u8]>>(bytes: T) -> [u8; 32] {
 
     output
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keystore_storage() {
+        let dir = std::env::temp_dir().join(format!("key_signer_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let keystore = KeyStorage::Keystore {
+            password: "password".to_string(),
+        };
+
+        let missing = dir.join("missing");
+        assert!(KeySigner::load(&missing, &keystore, false).is_err());
+        assert!(!missing.exists());
+
+        // A plaintext key is encrypted in place and keeps its identity.
+        let key_path = dir.join("0");
+        let plaintext = KeySigner::new(&key_path).unwrap();
+        let encrypted = KeySigner::load(&key_path, &keystore, false).unwrap();
+        assert_eq!(plaintext.get_public_key(), encrypted.get_public_key());
+        let content = std::fs::read_to_string(&key_path).unwrap();
+        assert!(content.starts_with('{'));
+
+        let reloaded = KeySigner::load(&key_path, &keystore, false).unwrap();
+        assert_eq!(plaintext.get_public_key(), reloaded.get_public_key());
+        let wrong_password = KeyStorage::Keystore {
+            password: "wrong".to_string(),
+        };
+        assert!(KeySigner::load(&key_path, &wrong_password, false).is_err());
+        assert!(KeySigner::load(&key_path, &KeyStorage::Plaintext, false).is_err());
+
+        let generated = dir.join("1");
+        KeySigner::load(&generated, &keystore, true).unwrap();
+        assert!(std::fs::read_to_string(&generated)
+            .unwrap()
+            .starts_with('{'));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
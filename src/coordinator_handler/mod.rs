@@ -2,9 +2,13 @@ mod api;
 mod coordinator_client;
 mod error;
 mod key_signer;
+pub mod remote_signer;
+mod signer;
 mod types;
 
 pub use coordinator_client::CoordinatorClient;
 pub use error::ErrorCode;
-pub use key_signer::KeySigner;
+pub use key_signer::{KeySigner, KeyStorage};
+pub use remote_signer::RemoteSigner;
+pub use signer::Signer;
 pub use types::*;
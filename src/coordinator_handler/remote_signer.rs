@@ -0,0 +1,138 @@
+//! A [`Signer`] delegating to an external signing service, so that private keys never reach the
+//! prover host.
+//!
+//! The protocol follows the eth1 signing endpoint of web3signer:
+//!
+//! | Method | Path                             | Request body      | Response body       |
+//! |--------|----------------------------------|-------------------|---------------------|
+//! | `POST` | `/api/v1/eth1/sign/{public_key}` | [`SignRequest`]   | `0x`-hex signature  |
+//!
+//! `data` is the RLP login message itself. The service signs its keccak256 hash, with no
+//! message prefix. The signature is the 65-byte `r || s || v`, with `v` either `0`/`1` or
+//! `27`/`28`. It is checked against the public key before use.
+
+use super::{key_signer::keccak256, Signer};
+use crate::config::RemoteSignerConfig;
+use async_trait::async_trait;
+use core::time::Duration;
+use ethers_core::{
+    k256::{
+        ecdsa::{RecoveryId, Signature, VerifyingKey},
+        elliptic_curve::sec1::ToEncodedPoint,
+        PublicKey,
+    },
+    utils::hex,
+};
+use reqwest::{header::CONTENT_TYPE, StatusCode, Url};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignRequest {
+    /// `0x`-hex of the message whose keccak256 hash is signed.
+    pub data: String,
+}
+
+pub struct RemoteSigner {
+    base_url: Url,
+    /// The public key as configured, identifying the key to the signing service.
+    identifier: String,
+    public_key: PublicKey,
+    send_timeout: Duration,
+    client: ClientWithMiddleware,
+}
+
+impl RemoteSigner {
+    /// Creates a signer for `public_key`, given as compressed or uncompressed SEC1 hex.
+    pub fn new(cfg: &RemoteSignerConfig, public_key: &str) -> anyhow::Result<Self> {
+        let key_bytes = hex::decode(public_key.trim_start_matches("0x"))?;
+        let parsed_key = PublicKey::from_sec1_bytes(&key_bytes)
+            .map_err(|e| anyhow::anyhow!("invalid remote signer public key {public_key}: {e}"))?;
+
+        let retry_wait_duration = Duration::from_secs(cfg.retry_wait_time_sec);
+        let retry_policy = ExponentialBackoff::builder()
+            .retry_bounds(retry_wait_duration / 2, retry_wait_duration)
+            .build_with_max_retries(cfg.retry_count);
+
+        let client = ClientBuilder::new(reqwest::Client::new())
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .build();
+
+        Ok(Self {
+            base_url: Url::parse(&cfg.base_url)?,
+            identifier: public_key.to_string(),
+            public_key: parsed_key,
+            send_timeout: Duration::from_secs(cfg.connection_timeout_sec),
+            client,
+        })
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let url = self
+            .base_url
+            .join(&format!("/api/v1/eth1/sign/{}", self.identifier))?;
+        let request_body = serde_json::to_string(&SignRequest {
+            data: format!("0x{}", hex::encode(message)),
+        })?;
+
+        log::info!("[remote signer], {}, sent request", url.path());
+        let response = self
+            .client
+            .post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(request_body)
+            .timeout(self.send_timeout)
+            .send()
+            .await?;
+        let status = response.status();
+        let response_body = response.text().await?;
+        if status != StatusCode::OK {
+            anyhow::bail!(
+                "[remote signer], sign request failed with status {status}: {response_body}"
+            );
+        }
+
+        let signature = hex::decode(
+            response_body
+                .trim()
+                .trim_matches('"')
+                .trim_start_matches("0x"),
+        )?;
+        self.verify(&keccak256(message), signature)
+    }
+
+    /// Checks that `signature` is by this signer's key, normalizing `v` to `0`/`1`.
+    fn verify(&self, hash: &[u8; 32], mut signature: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        anyhow::ensure!(
+            signature.len() == 65,
+            "[remote signer], expected a 65-byte signature, got {} bytes",
+            signature.len()
+        );
+        if signature[64] >= 27 {
+            signature[64] -= 27;
+        }
+        let recovery_id = RecoveryId::from_byte(signature[64])
+            .ok_or_else(|| anyhow::anyhow!("[remote signer], invalid signature recovery id"))?;
+        let recoverable_sig = Signature::from_slice(&signature[..64])?;
+        let recovered = VerifyingKey::recover_from_prehash(hash, &recoverable_sig, recovery_id)?;
+        anyhow::ensure!(
+            recovered.to_encoded_point(true) == self.public_key.to_encoded_point(true),
+            "[remote signer], signature does not match public key {}",
+            self.identifier
+        );
+        Ok(signature)
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    fn get_public_key(&self) -> String {
+        hex::encode(self.public_key.to_encoded_point(true).as_bytes())
+    }
+
+    async fn sign_buffer(&self, buffer: &[u8]) -> anyhow::Result<String> {
+        let signature = self.sign_message(buffer).await?;
+        Ok(format!("0x{}", hex::encode(signature)))
+    }
+}
@@ -0,0 +1,23 @@
+use super::KeySigner;
+use async_trait::async_trait;
+
+/// Holds a prover identity and signs the login messages proving ownership of it.
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// Returns the compressed secp256k1 public key as hex, without `0x`.
+    fn get_public_key(&self) -> String;
+
+    /// Signs the keccak256 hash of `buffer`, returning the `r || s || v` signature as `0x` hex.
+    async fn sign_buffer(&self, buffer: &[u8]) -> anyhow::Result<String>;
+}
+
+#[async_trait]
+impl Signer for KeySigner {
+    fn get_public_key(&self) -> String {
+        KeySigner::get_public_key(self)
+    }
+
+    async fn sign_buffer(&self, buffer: &[u8]) -> anyhow::Result<String> {
+        KeySigner::sign_buffer(self, &buffer)
+    }
+}
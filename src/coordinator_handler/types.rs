@@ -6,7 +6,7 @@ use crate::{
 use rlp::{Encodable, RlpStream};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Response<T> {
     pub errcode: ErrorCode,
     pub errmsg: String,
@@ -60,7 +60,7 @@ impl<'de> Deserialize<'de> for ProverType {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct LoginMessage {
     pub challenge: String,
     pub prover_version: String,
@@ -130,7 +130,7 @@ pub struct ChunkTaskDetail {
     pub fork_name: String,
 }
 
-#[derive(Serialize, Deserialize)] // TODO: Default?
+#[derive(Serialize, Deserialize, Clone)] // TODO: Default?
 pub struct SubmitProofRequest {
     pub uuid: String,
     pub task_id: String,
@@ -1,16 +1,158 @@
-use crate::coordinator_handler::GetTaskResponseData;
-use rocksdb::DB;
+use crate::{
+    coordinator_handler::{ErrorCode, GetTaskResponseData, SubmitProofRequest},
+    prover::{CircuitType, ProofType},
+    tracing_handler::CommonHash,
+};
+use rocksdb::{Direction, IteratorMode, Options, WriteBatch, DB};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One coordinator task handled by this prover, kept after the task is done. Timestamps are
+/// Unix seconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRecord {
+    pub public_key: String,
+    pub uuid: String,
+    pub task_id: String,
+    pub proof_type: ProofType,
+    pub hard_fork_name: String,
+    pub proving_task_id: Option<String>,
+    pub fetched_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    pub submitted_at: Option<u64>,
+    pub outcome: Option<TaskRecordOutcome>,
+    pub failure_msg: Option<String>,
+    /// Response code of the last submission the coordinator answered.
+    pub submission_errcode: Option<ErrorCode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskRecordOutcome {
+    Proved,
+    Failed,
+}
+
+/// Criteria for [`Db::list_task_history`]. Unset fields match every record.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TaskHistoryFilter {
+    pub public_key: Option<String>,
+    pub proof_type: Option<ProofType>,
+    pub outcome: Option<TaskRecordOutcome>,
+    /// Only records fetched at or after this Unix timestamp.
+    pub since: Option<u64>,
+    /// Only records fetched before this Unix timestamp.
+    pub until: Option<u64>,
+    /// Maximum number of records returned, capped at `MAX_TASK_HISTORY_LIMIT`.
+    pub limit: Option<usize>,
+    /// Only records older than this, the `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+}
+
+/// A page of [`Db::list_task_history`] results, newest first.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskHistoryPage {
+    pub records: Vec<TaskRecord>,
+    /// Set if older records may remain, to be passed as `cursor` to list them.
+    pub next_cursor: Option<String>,
+}
+
+const DEFAULT_TASK_HISTORY_LIMIT: usize = 100;
+const MAX_TASK_HISTORY_LIMIT: usize = 1000;
+/// Index entries read by one `list_task_history` call, bounding the work of filters that match
+/// few records.
+const MAX_TASK_HISTORY_SCAN: usize = 10_000;
+
+impl TaskHistoryFilter {
+    fn matches(&self, record: &TaskRecord) -> bool {
+        self.public_key
+            .as_ref()
+            .is_none_or(|public_key| *public_key == record.public_key)
+            && self
+                .proof_type
+                .is_none_or(|proof_type| proof_type == record.proof_type)
+            && self
+                .outcome
+                .is_none_or(|outcome| Some(outcome) == record.outcome)
+    }
+}
 
 pub struct Db {
     db: DB,
 }
 
+/// Column family holding block traces and witnesses fetched from l2geth.
+const BLOCK_CACHE_CF: &str = "block_cache";
+
 impl Db {
     pub fn new(path: &str) -> anyhow::Result<Self> {
-        let db = DB::open_default(path)?;
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = DB::open_cf(&opts, path, [BLOCK_CACHE_CF])?;
         Ok(Self { db })
     }
 
+    /// Returns the cached block data for `circuit_type`, unless it is older than `max_age_sec`.
+    pub fn get_cached_block(
+        &self,
+        circuit_type: CircuitType,
+        hash: &CommonHash,
+        max_age_sec: u64,
+    ) -> Option<Vec<u8>> {
+        let cf = self.db.cf_handle(BLOCK_CACHE_CF)?;
+        let value = self
+            .db
+            .get_cf(cf, fmt_block_cache_key(circuit_type, hash))
+            .ok()??;
+        let (stored_at, data) = split_block_cache_value(&value)?;
+        if now().saturating_sub(stored_at) > max_age_sec {
+            return None;
+        }
+        Some(data.to_vec())
+    }
+
+    pub fn put_cached_block(&self, circuit_type: CircuitType, hash: &CommonHash, data: &[u8]) {
+        let Some(cf) = self.db.cf_handle(BLOCK_CACHE_CF) else {
+            return;
+        };
+        let mut value = now().to_be_bytes().to_vec();
+        value.extend_from_slice(data);
+        if let Err(e) = self
+            .db
+            .put_cf(cf, fmt_block_cache_key(circuit_type, hash), value)
+        {
+            log::error!("[db], failed to put cached block: {e}");
+        }
+    }
+
+    /// Deletes the cached blocks older than `max_age_sec`, returning how many were deleted.
+    pub fn evict_block_cache(&self, max_age_sec: u64) -> usize {
+        let Some(cf) = self.db.cf_handle(BLOCK_CACHE_CF) else {
+            return 0;
+        };
+        let now = now();
+        let mut batch = WriteBatch::default();
+        for (key, value) in self
+            .db
+            .iterator_cf(cf, IteratorMode::Start)
+            .map_while(|item| item.ok())
+        {
+            let expired = split_block_cache_value(&value)
+                .is_none_or(|(stored_at, _)| now.saturating_sub(stored_at) > max_age_sec);
+            if expired {
+                batch.delete_cf(cf, key);
+            }
+        }
+        let evicted = batch.len();
+        if let Err(e) = self.db.write(batch) {
+            log::error!("[db], failed to evict block cache: {e}");
+            return 0;
+        }
+        evicted
+    }
+
     pub fn get_task(&self, public_key: String) -> (Option<GetTaskResponseData>, Option<String>) {
         log::debug!("[db], get task, public_key: {public_key}");
         (
@@ -19,6 +161,8 @@ impl Db {
         )
     }
 
+    /// Persists the coordinator task and its proving task id in a single atomic write, so an
+    /// interrupted process never leaves only one of the two keys behind.
     pub fn set_task(
         &self,
         public_key: String,
@@ -26,13 +170,216 @@ impl Db {
         proving_task_id: String,
     ) {
         log::debug!("[db], set task, public_key: {public_key}");
-        self.set_coordinator_task_by_public_key(public_key.clone(), coordinator_task);
-        self.set_proving_task_id_by_public_key(public_key, proving_task_id);
+        let coordinator_task = match serde_json::to_vec(coordinator_task) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::error!("[db], failed to serialize coordinator task: {e}");
+                return;
+            }
+        };
+        let mut batch = WriteBatch::default();
+        batch.put(
+            fmt_coordinator_task_key(public_key.clone()),
+            coordinator_task,
+        );
+        batch.put(
+            fmt_proving_task_id_key(public_key),
+            proving_task_id.as_bytes(),
+        );
+        if let Err(e) = self.db.write(batch) {
+            log::error!("[db], failed to set task: {e}");
+        }
     }
 
     pub fn delete_task(&self, public_key: String) {
-        self.delete_coordinator_task_by_public_key(public_key.clone());
-        self.delete_proving_task_id_by_public_key(public_key);
+        let mut batch = WriteBatch::default();
+        batch.delete(fmt_coordinator_task_key(public_key.clone()));
+        batch.delete(fmt_proving_task_id_key(public_key));
+        if let Err(e) = self.db.write(batch) {
+            log::error!("[db], failed to delete task: {e}");
+        }
+    }
+
+    /// Stores a proof submission the coordinator could not be reached for, to be retried later.
+    pub fn add_pending_submission(&self, public_key: String, submission: &SubmitProofRequest) {
+        log::debug!(
+            "[db], add pending submission, public_key: {public_key}, uuid: {}",
+            submission.uuid
+        );
+        let submission_bytes = match serde_json::to_vec(submission) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::error!("[db], failed to serialize pending submission: {e}");
+                return;
+            }
+        };
+        if let Err(e) = self.db.put(
+            fmt_pending_submission_key(public_key, &submission.uuid),
+            submission_bytes,
+        ) {
+            log::error!("[db], failed to add pending submission: {e}");
+        }
+    }
+
+    pub fn get_pending_submissions(&self, public_key: String) -> Vec<SubmitProofRequest> {
+        let prefix = fmt_pending_submission_prefix(public_key);
+        self.db
+            .iterator(IteratorMode::From(prefix.as_bytes(), Direction::Forward))
+            .map_while(|item| item.ok())
+            .take_while(|(key, _)| key.starts_with(prefix.as_bytes()))
+            .filter_map(|(_, value)| serde_json::from_slice(&value).ok())
+            .collect()
+    }
+
+    pub fn delete_pending_submission(&self, public_key: String, uuid: &str) {
+        if let Err(e) = self.db.delete(fmt_pending_submission_key(public_key, uuid)) {
+            log::error!("[db], failed to delete pending submission: {e}");
+        }
+    }
+
+    /// Starts the history record of a task fetched from the coordinator. Records are never
+    /// deleted.
+    pub fn record_task_fetched(&self, public_key: String, task: &GetTaskResponseData) {
+        let key = fmt_task_history_key(&task.uuid);
+        if matches!(self.db.get(&key), Ok(Some(_))) {
+            return;
+        }
+        let fetched_at = now();
+        self.put_task_record(&TaskRecord {
+            public_key,
+            uuid: task.uuid.clone(),
+            task_id: task.task_id.clone(),
+            proof_type: task.task_type,
+            hard_fork_name: task.hard_fork_name.clone(),
+            proving_task_id: None,
+            fetched_at,
+            started_at: None,
+            finished_at: None,
+            submitted_at: None,
+            outcome: None,
+            failure_msg: None,
+            submission_errcode: None,
+        });
+        if let Err(e) = self.db.put(
+            fmt_task_history_index_key(fetched_at, &task.uuid),
+            task.uuid.as_bytes(),
+        ) {
+            log::error!("[db], failed to index task record: {e}");
+        }
+    }
+
+    pub fn record_task_started(&self, uuid: &str, proving_task_id: String) {
+        self.update_task_record(uuid, |record| {
+            record.proving_task_id = Some(proving_task_id);
+            record.started_at = Some(now());
+        });
+    }
+
+    pub fn record_task_finished(
+        &self,
+        uuid: &str,
+        outcome: TaskRecordOutcome,
+        failure_msg: Option<String>,
+    ) {
+        self.update_task_record(uuid, |record| {
+            record.finished_at = Some(now());
+            record.outcome = Some(outcome);
+            record.failure_msg = failure_msg;
+        });
+    }
+
+    /// Records the coordinator's answer to a proof submission.
+    pub fn record_task_submitted(&self, uuid: &str, errcode: ErrorCode) {
+        self.update_task_record(uuid, |record| {
+            record.submitted_at = Some(now());
+            record.submission_errcode = Some(errcode);
+        });
+    }
+
+    pub fn get_task_record(&self, uuid: &str) -> Option<TaskRecord> {
+        self.db
+            .get(fmt_task_history_key(uuid))
+            .ok()?
+            .as_ref()
+            .and_then(|v| serde_json::from_slice(v).ok())
+    }
+
+    /// Lists the history records matching `filter`, newest first, walking the index ordered by
+    /// fetch time backwards from `filter.cursor` or `filter.until`.
+    pub fn list_task_history(&self, filter: &TaskHistoryFilter) -> TaskHistoryPage {
+        let limit = filter
+            .limit
+            .unwrap_or(DEFAULT_TASK_HISTORY_LIMIT)
+            .min(MAX_TASK_HISTORY_LIMIT);
+        let prefix = TASK_HISTORY_INDEX_PREFIX.as_bytes();
+        // Index keys are compared bytewise, so '~' sorts after every timestamp.
+        let mut end = format!("{TASK_HISTORY_INDEX_PREFIX}~");
+        if let Some(until) = filter.until {
+            end = format!("{TASK_HISTORY_INDEX_PREFIX}{until:020}");
+        }
+        if let Some(cursor) = &filter.cursor {
+            end = end.min(format!("{TASK_HISTORY_INDEX_PREFIX}{cursor}"));
+        }
+
+        let mut page = TaskHistoryPage {
+            records: vec![],
+            next_cursor: None,
+        };
+        let index = self
+            .db
+            .iterator(IteratorMode::From(end.as_bytes(), Direction::Reverse))
+            .map_while(|item| item.ok())
+            .skip_while(|(key, _)| key.as_ref() >= end.as_bytes())
+            .take_while(|(key, _)| key.starts_with(prefix));
+        let mut last_cursor = None;
+        for (scanned, (key, uuid)) in index.enumerate() {
+            if page.records.len() == limit || scanned == MAX_TASK_HISTORY_SCAN {
+                page.next_cursor = last_cursor;
+                break;
+            }
+            let cursor = String::from_utf8_lossy(&key[prefix.len()..]).into_owned();
+            let fetched_at = cursor.get(..20).and_then(|at| at.parse::<u64>().ok());
+            if filter
+                .since
+                .is_some_and(|since| fetched_at.is_some_and(|at| at < since))
+            {
+                break;
+            }
+            if let Some(record) = std::str::from_utf8(&uuid)
+                .ok()
+                .and_then(|uuid| self.get_task_record(uuid))
+                .filter(|record| filter.matches(record))
+            {
+                page.records.push(record);
+            }
+            last_cursor = Some(cursor);
+        }
+        page
+    }
+
+    fn update_task_record(&self, uuid: &str, update: impl FnOnce(&mut TaskRecord)) {
+        let Some(mut record) = self.get_task_record(uuid) else {
+            log::warn!("[db], no history record for task, uuid: {uuid}");
+            return;
+        };
+        update(&mut record);
+        self.put_task_record(&record);
+    }
+
+    fn put_task_record(&self, record: &TaskRecord) {
+        let record_bytes = match serde_json::to_vec(record) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::error!("[db], failed to serialize task record: {e}");
+                return;
+            }
+        };
+        if let Err(e) = self
+            .db
+            .put(fmt_task_history_key(&record.uuid), record_bytes)
+        {
+            log::error!("[db], failed to put task record: {e}");
+        }
     }
 
     fn get_coordinator_task_by_public_key(
@@ -52,36 +399,182 @@ impl Db {
             .ok()?
             .and_then(|v| String::from_utf8(v).ok())
     }
+}
 
-    fn set_coordinator_task_by_public_key(
-        &self,
-        public_key: String,
-        coordinator_task: &GetTaskResponseData,
-    ) {
-        let _ = serde_json::to_vec(coordinator_task)
-            .map(|bytes| self.db.put(fmt_coordinator_task_key(public_key), bytes));
-    }
+fn fmt_coordinator_task_key(public_key: String) -> String {
+    format!("last_coordinator_task_{}", public_key)
+}
 
-    fn set_proving_task_id_by_public_key(&self, public_key: String, proving_task_id: String) {
-        let _ = self.db.put(
-            fmt_proving_task_id_key(public_key),
-            proving_task_id.as_bytes(),
-        );
+fn fmt_proving_task_id_key(public_key: String) -> String {
+    format!("last_proving_task_id_{}", public_key)
+}
+
+fn fmt_block_cache_key(circuit_type: CircuitType, hash: &CommonHash) -> String {
+    format!("{}_{:#x}", circuit_type.to_u8(), hash)
+}
+
+/// Block cache values are the Unix timestamp they were stored at, big-endian, followed by the
+/// data.
+fn split_block_cache_value(value: &[u8]) -> Option<(u64, &[u8])> {
+    let (stored_at, data) = value.split_first_chunk::<8>()?;
+    Some((u64::from_be_bytes(*stored_at), data))
+}
+
+const TASK_HISTORY_PREFIX: &str = "task_history_";
+/// Not starting with `TASK_HISTORY_PREFIX`, so that index keys never look like records.
+const TASK_HISTORY_INDEX_PREFIX: &str = "task_fetched_at_";
+
+fn fmt_task_history_key(uuid: &str) -> String {
+    format!("{}{}", TASK_HISTORY_PREFIX, uuid)
+}
+
+/// Orders the history by fetch time: the timestamp is zero-padded so that keys sort by it.
+fn fmt_task_history_index_key(fetched_at: u64, uuid: &str) -> String {
+    format!("{}{:020}_{}", TASK_HISTORY_INDEX_PREFIX, fetched_at, uuid)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn fmt_pending_submission_prefix(public_key: String) -> String {
+    format!("pending_submission_{}_", public_key)
+}
+
+fn fmt_pending_submission_key(public_key: String, uuid: &str) -> String {
+    format!("{}{}", fmt_pending_submission_prefix(public_key), uuid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{coordinator_handler::ProofStatus, prover::ProofType};
+
+    fn submission(uuid: &str) -> SubmitProofRequest {
+        SubmitProofRequest {
+            uuid: uuid.to_string(),
+            task_id: format!("task_{uuid}"),
+            task_type: ProofType::Batch,
+            status: ProofStatus::Ok,
+            proof: "proof".to_string(),
+            failure_type: None,
+            failure_msg: None,
+        }
     }
 
-    fn delete_coordinator_task_by_public_key(&self, public_key: String) {
-        let _ = self.db.delete(fmt_coordinator_task_key(public_key));
+    #[test]
+    fn test_pending_submissions_are_scoped_by_public_key() {
+        let path =
+            std::env::temp_dir().join(format!("pending_submissions_{}", rand::random::<u64>()));
+        {
+            let db = Db::new(path.to_str().unwrap()).unwrap();
+            db.add_pending_submission("key_a".to_string(), &submission("1"));
+            db.add_pending_submission("key_a".to_string(), &submission("2"));
+            db.add_pending_submission("key_b".to_string(), &submission("3"));
+        }
+
+        // Pending submissions survive reopening the db.
+        let db = Db::new(path.to_str().unwrap()).unwrap();
+        let uuids: Vec<String> = db
+            .get_pending_submissions("key_a".to_string())
+            .into_iter()
+            .map(|s| s.uuid)
+            .collect();
+        assert_eq!(uuids, vec!["1".to_string(), "2".to_string()]);
+
+        db.delete_pending_submission("key_a".to_string(), "1");
+        assert_eq!(db.get_pending_submissions("key_a".to_string()).len(), 1);
+        assert_eq!(db.get_pending_submissions("key_b".to_string()).len(), 1);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
     }
 
-    fn delete_proving_task_id_by_public_key(&self, public_key: String) {
-        let _ = self.db.delete(fmt_proving_task_id_key(public_key));
+    #[test]
+    fn test_block_cache_is_scoped_by_circuit_type_and_evicted_by_age() {
+        let path = std::env::temp_dir().join(format!("block_cache_{}", rand::random::<u64>()));
+        let db = Db::new(path.to_str().unwrap()).unwrap();
+        let hash = CommonHash::repeat_byte(1);
+
+        db.put_cached_block(CircuitType::Halo2, &hash, b"trace");
+        assert_eq!(
+            db.get_cached_block(CircuitType::Halo2, &hash, 60),
+            Some(b"trace".to_vec())
+        );
+        assert_eq!(db.get_cached_block(CircuitType::OpenVM, &hash, 60), None);
+
+        assert_eq!(db.evict_block_cache(60), 0);
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        assert_eq!(db.get_cached_block(CircuitType::Halo2, &hash, 0), None);
+        assert_eq!(db.evict_block_cache(0), 1);
+        assert_eq!(db.get_cached_block(CircuitType::Halo2, &hash, 60), None);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
     }
-}
 
-fn fmt_coordinator_task_key(public_key: String) -> String {
-    format!("last_coordinator_task_{}", public_key)
-}
+    #[test]
+    fn test_task_history_lifecycle_and_filter() {
+        let path = std::env::temp_dir().join(format!("task_history_{}", rand::random::<u64>()));
+        let db = Db::new(path.to_str().unwrap()).unwrap();
+        let task = |uuid: &str, task_type| GetTaskResponseData {
+            uuid: uuid.to_string(),
+            task_id: format!("task_{uuid}"),
+            task_type,
+            ..Default::default()
+        };
 
-fn fmt_proving_task_id_key(public_key: String) -> String {
-    format!("last_proving_task_id_{}", public_key)
+        db.record_task_fetched("key_a".to_string(), &task("1", ProofType::Batch));
+        db.record_task_started("1", "proving_1".to_string());
+        db.record_task_finished("1", TaskRecordOutcome::Proved, None);
+        db.record_task_submitted("1", ErrorCode::Success);
+        db.record_task_fetched("key_b".to_string(), &task("2", ProofType::Chunk));
+        db.record_task_finished("2", TaskRecordOutcome::Failed, Some("panic".to_string()));
+
+        let record = db.get_task_record("1").unwrap();
+        assert_eq!(record.proving_task_id.as_deref(), Some("proving_1"));
+        assert_eq!(record.outcome, Some(TaskRecordOutcome::Proved));
+        assert_eq!(record.submission_errcode, Some(ErrorCode::Success));
+        assert!(record.submitted_at.is_some());
+
+        let all = db.list_task_history(&TaskHistoryFilter::default());
+        assert_eq!(all.records.len(), 2);
+        assert!(all.next_cursor.is_none());
+        let failed = db.list_task_history(&TaskHistoryFilter {
+            outcome: Some(TaskRecordOutcome::Failed),
+            ..Default::default()
+        });
+        assert_eq!(failed.records.len(), 1);
+        assert_eq!(failed.records[0].uuid, "2");
+        let key_a = db.list_task_history(&TaskHistoryFilter {
+            public_key: Some("key_a".to_string()),
+            proof_type: Some(ProofType::Batch),
+            ..Default::default()
+        });
+        assert_eq!(key_a.records.len(), 1);
+
+        // Pages follow each other without gaps or repeats.
+        let mut uuids = vec![];
+        let mut cursor = None;
+        loop {
+            let page = db.list_task_history(&TaskHistoryFilter {
+                limit: Some(1),
+                cursor,
+                ..Default::default()
+            });
+            uuids.extend(page.records.into_iter().map(|record| record.uuid));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        uuids.sort();
+        assert_eq!(uuids, vec!["1".to_string(), "2".to_string()]);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
 }
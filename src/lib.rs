@@ -1,6 +1,9 @@
 pub mod config;
 pub mod coordinator_handler;
 pub mod db;
+pub mod metrics;
 pub mod prover;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod tracing_handler;
 pub mod utils;
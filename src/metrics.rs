@@ -0,0 +1,196 @@
+use crate::{
+    coordinator_handler::{ErrorCode, Response},
+    prover::{CircuitType, ProofType},
+};
+use axum::{http::header::CONTENT_TYPE, response::IntoResponse};
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, Encoder, HistogramVec, IntCounterVec,
+    TextEncoder,
+};
+use std::sync::LazyLock;
+use std::time::Instant;
+
+static TASKS_FETCHED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "scroll_prover_tasks_fetched_total",
+        "Tasks fetched from the coordinator",
+        &["proof_type", "worker"]
+    )
+    .unwrap()
+});
+
+static TASKS_PROVED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "scroll_prover_tasks_proved_total",
+        "Tasks proved successfully by the proving service",
+        &["proof_type", "worker"]
+    )
+    .unwrap()
+});
+
+static TASKS_FAILED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "scroll_prover_tasks_failed_total",
+        "Tasks reported to the coordinator as failed",
+        &["proof_type", "worker"]
+    )
+    .unwrap()
+});
+
+static TASKS_SUBMITTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "scroll_prover_tasks_submitted_total",
+        "Proof submissions accepted by the coordinator",
+        &["proof_type", "worker"]
+    )
+    .unwrap()
+});
+
+static INPUT_BUILD_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "scroll_prover_input_build_duration_seconds",
+        "Time spent building proving inputs",
+        &["proof_type"],
+        vec![0.1, 0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0]
+    )
+    .unwrap()
+});
+
+static PROVING_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "scroll_prover_proving_duration_seconds",
+        "Compute time reported by the proving service for successful tasks",
+        &["proof_type"],
+        vec![30.0, 60.0, 120.0, 300.0, 600.0, 1200.0, 1800.0, 3600.0, 7200.0, 14400.0]
+    )
+    .unwrap()
+});
+
+static COORDINATOR_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "scroll_prover_coordinator_request_duration_seconds",
+        "Latency of coordinator requests",
+        &["method"]
+    )
+    .unwrap()
+});
+
+static COORDINATOR_REQUEST_ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "scroll_prover_coordinator_request_errors_total",
+        "Failed coordinator requests, by coordinator error code or `http` for transport errors",
+        &["method", "errcode"]
+    )
+    .unwrap()
+});
+
+static L2GETH_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "scroll_prover_l2geth_request_duration_seconds",
+        "Latency of l2geth requests",
+        &["method"]
+    )
+    .unwrap()
+});
+
+static L2GETH_REQUEST_ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "scroll_prover_l2geth_request_errors_total",
+        "Failed l2geth requests",
+        &["method"]
+    )
+    .unwrap()
+});
+
+static BLOCK_CACHE_LOOKUPS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "scroll_prover_block_cache_lookups_total",
+        "Lookups in the on-disk cache of block traces and witnesses",
+        &["circuit_type", "result"]
+    )
+    .unwrap()
+});
+
+fn proof_type_label(proof_type: ProofType) -> String {
+    format!("{proof_type:?}")
+}
+
+pub fn inc_tasks_fetched(proof_type: ProofType, worker: &str) {
+    TASKS_FETCHED
+        .with_label_values(&[&proof_type_label(proof_type), worker])
+        .inc();
+}
+
+pub fn inc_tasks_proved(proof_type: ProofType, worker: &str) {
+    TASKS_PROVED
+        .with_label_values(&[&proof_type_label(proof_type), worker])
+        .inc();
+}
+
+pub fn inc_tasks_failed(proof_type: ProofType, worker: &str) {
+    TASKS_FAILED
+        .with_label_values(&[&proof_type_label(proof_type), worker])
+        .inc();
+}
+
+pub fn inc_tasks_submitted(proof_type: ProofType, worker: &str) {
+    TASKS_SUBMITTED
+        .with_label_values(&[&proof_type_label(proof_type), worker])
+        .inc();
+}
+
+pub fn observe_input_build(proof_type: ProofType, started: Instant) {
+    INPUT_BUILD_DURATION
+        .with_label_values(&[&proof_type_label(proof_type)])
+        .observe(started.elapsed().as_secs_f64());
+}
+
+pub fn observe_proving(proof_type: ProofType, compute_time_sec: f64) {
+    PROVING_DURATION
+        .with_label_values(&[&proof_type_label(proof_type)])
+        .observe(compute_time_sec);
+}
+
+pub fn observe_coordinator_request<T>(
+    method: &str,
+    started: Instant,
+    result: &anyhow::Result<Response<T>>,
+) {
+    COORDINATOR_REQUEST_DURATION
+        .with_label_values(&[method])
+        .observe(started.elapsed().as_secs_f64());
+    let errcode = match result {
+        Ok(response) if response.errcode == ErrorCode::Success => return,
+        Ok(response) => format!("{:?}", response.errcode),
+        Err(_) => "http".to_string(),
+    };
+    COORDINATOR_REQUEST_ERRORS
+        .with_label_values(&[method, &errcode])
+        .inc();
+}
+
+pub fn observe_l2geth_request<T>(method: &str, started: Instant, result: &anyhow::Result<T>) {
+    L2GETH_REQUEST_DURATION
+        .with_label_values(&[method])
+        .observe(started.elapsed().as_secs_f64());
+    if result.is_err() {
+        L2GETH_REQUEST_ERRORS.with_label_values(&[method]).inc();
+    }
+}
+
+pub fn inc_block_cache_lookup(circuit_type: CircuitType, hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    BLOCK_CACHE_LOOKUPS
+        .with_label_values(&[&format!("{circuit_type:?}"), result])
+        .inc();
+}
+
+/// Serves every registered metric in the Prometheus text exposition format.
+pub async fn metrics_handler() -> impl IntoResponse {
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+    if let Err(e) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        log::error!("failed to encode metrics: {e}");
+    }
+    ([(CONTENT_TYPE, encoder.format_type().to_string())], buffer)
+}
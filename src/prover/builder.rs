@@ -1,22 +1,26 @@
-use tokio::sync::RwLock;
+use tokio::sync::{watch, Semaphore};
 
-use super::{ProofType, ProverProviderType};
+use super::{reload::ConfigSource, ProverProviderType};
 use crate::{
     config::Config,
-    coordinator_handler::{CoordinatorClient, KeySigner},
+    coordinator_handler::{CoordinatorClient, KeySigner, KeyStorage, RemoteSigner, Signer},
     db::Db,
     prover::{
         proving_service::{GetVkRequest, ProvingService},
-        Prover,
+        Network, Prover,
     },
     tracing_handler::L2gethClient,
     utils::format_cloud_prover_name,
 };
-use std::path::PathBuf;
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex as StdMutex, RwLock as StdRwLock},
+};
 
 pub struct ProverBuilder<Backend: ProvingService + Send + Sync + 'static> {
     cfg: Config,
     proving_service: Backend,
+    config_source: Option<ConfigSource>,
 }
 
 impl<Backend> ProverBuilder<Backend>
@@ -27,96 +31,167 @@ where
         ProverBuilder {
             cfg,
             proving_service: service,
+            config_source: None,
         }
     }
 
-    pub async fn build(self) -> anyhow::Result<Prover<Backend>> {
-        if self.proving_service.is_local() && self.cfg.prover.n_workers > 1 {
-            anyhow::bail!("cannot use multiple workers with local proving service");
-        }
-
-        if self
-            .cfg
-            .prover
-            .supported_proof_types
-            .contains(&ProofType::Chunk)
-            && self.cfg.l2geth.is_none()
-        {
-            anyhow::bail!("circuit_type is chunk but l2geth config is not provided");
-        }
+    /// Reloads the config with `load` on SIGHUP or when any of `files` changes, applying the
+    /// settings that can change while running. See `Prover::reload_config`.
+    pub fn with_config_reload<F>(mut self, files: Vec<PathBuf>, load: F) -> Self
+    where
+        F: Fn() -> anyhow::Result<Config> + Send + Sync + 'static,
+    {
+        self.config_source = Some(ConfigSource::new(files, load));
+        self
+    }
 
-        let get_vk_request = GetVkRequest {
-            proof_types: self.cfg.prover.supported_proof_types.clone(),
-            circuit_version: self.cfg.prover.circuit_version.clone(),
-        };
-        let get_vk_response = self.proving_service.get_vks(get_vk_request).await;
-        if let Some(error) = get_vk_response.error {
-            anyhow::bail!("failed to get vk: {}", error);
+    pub async fn build(self) -> anyhow::Result<Prover<Backend>> {
+        self.cfg.validate()?;
+        let network_configs = self.cfg.networks()?;
+        let total_workers: usize = network_configs
+            .iter()
+            .map(|network| network.n_workers.unwrap_or_default())
+            .sum();
+        let is_local = self.proving_service.is_local();
+        let max_concurrency = self.proving_service.max_concurrency().max(1);
+        if is_local && total_workers > max_concurrency {
+            anyhow::bail!(
+                "cannot use {total_workers} workers with a local proving service that proves at most {max_concurrency} tasks at once"
+            );
         }
 
-        let prover_provider_type = if self.proving_service.is_local() {
+        let prover_provider_type = if is_local {
             ProverProviderType::Internal
         } else {
             ProverProviderType::External
         };
 
-        let key_signers: Result<Vec<_>, _> = (0..self.cfg.prover.n_workers)
-            .map(|i| {
-                let keys_dir = PathBuf::from(&self.cfg.keys_dir);
-                if !keys_dir.exists() {
-                    std::fs::create_dir_all(&keys_dir).map_err(|e| {
-                        anyhow::anyhow!(
-                            "failed to create keys directory {}: {e}",
-                            keys_dir.display()
-                        )
-                    })?;
-                }
-                let key_path = keys_dir.join(i.to_string());
-                KeySigner::new(&key_path)
-            })
-            .collect();
-        let key_signers =
-            key_signers.map_err(|e| anyhow::anyhow!("cannot create key_signer, err: {e}"))?;
+        let key_storage = match &self.cfg.keystore {
+            Some(keystore) => KeyStorage::Keystore {
+                password: keystore.password()?,
+            },
+            None => KeyStorage::Plaintext,
+        };
+
+        let mut networks = vec![];
+        let mut coordinator_clients = vec![];
+        let mut worker_networks = vec![];
+        for network_config in network_configs {
+            let name = network_config.name;
+            let proof_types = network_config.supported_proof_types.unwrap_or_default();
+            let n_workers = network_config.n_workers.unwrap_or_default();
+
+            let get_vk_request = GetVkRequest {
+                proof_types: proof_types.clone(),
+                circuit_version: self.cfg.prover.circuit_version.clone(),
+            };
+            let get_vk_response = self.proving_service.get_vks(get_vk_request).await;
+            if let Some(error) = get_vk_response.error {
+                anyhow::bail!("network {name}: failed to get vk: {}", error);
+            }
 
-        let coordinator_clients: Result<Vec<_>, _> = (0..self.cfg.prover.n_workers)
-            .map(|i| {
-                let prover_name = if self.proving_service.is_local() {
-                    self.cfg.prover_name_prefix.clone()
+            let keys_dir = PathBuf::from(&self.cfg.keys_dir)
+                .join(network_config.keys_subdir.unwrap_or_default());
+            if !keys_dir.exists() {
+                std::fs::create_dir_all(&keys_dir).map_err(|e| {
+                    anyhow::anyhow!(
+                        "failed to create keys directory {}: {e}",
+                        keys_dir.display()
+                    )
+                })?;
+            }
+
+            // The single implicit network keeps the prover names used before networks existed.
+            let prover_name_prefix = if self.cfg.networks.is_empty() {
+                self.cfg.prover_name_prefix.clone()
+            } else {
+                format!("{}_{name}", self.cfg.prover_name_prefix)
+            };
+            for i in 0..n_workers {
+                let signer: Arc<dyn Signer> = match &self.cfg.remote_signer {
+                    Some(remote_signer) => {
+                        let public_key = remote_signer
+                            .public_keys
+                            .get(coordinator_clients.len())
+                            .ok_or_else(|| {
+                            anyhow::anyhow!("remote signer has fewer public keys than workers")
+                        })?;
+                        Arc::new(RemoteSigner::new(remote_signer, public_key)?)
+                    }
+                    None => Arc::new(
+                        KeySigner::load(
+                            &keys_dir.join(i.to_string()),
+                            &key_storage,
+                            self.cfg.generate_missing_keys,
+                        )
+                        .map_err(|e| anyhow::anyhow!("cannot create key_signer, err: {e}"))?,
+                    ),
+                };
+                // A single local worker keeps the name used before local workers were indexed.
+                let prover_name = if is_local && total_workers == 1 {
+                    prover_name_prefix.clone()
+                } else if is_local {
+                    format!("{prover_name_prefix}_{i}")
                 } else {
-                    format_cloud_prover_name(self.cfg.prover_name_prefix.clone(), i)
+                    format_cloud_prover_name(prover_name_prefix.clone(), i)
                 };
 
-                CoordinatorClient::new(
-                    self.cfg.coordinator.clone(),
-                    self.cfg.coordinator_prover_type(),
+                coordinator_clients.push(StdRwLock::new(Arc::new(CoordinatorClient::new(
+                    network_config.coordinator.clone(),
+                    self.cfg.prover_types_for(&proof_types),
                     get_vk_response.vks.clone(),
                     prover_name,
                     prover_provider_type,
-                    key_signers[i].clone(),
-                )
-            })
-            .collect();
-        let coordinator_clients = coordinator_clients?;
+                    signer,
+                )?)));
+                worker_networks.push(networks.len());
+            }
 
-        let l2geth_client = match self.cfg.l2geth {
-            Some(l2geth) => Some(L2gethClient::new(l2geth)?),
-            None => None,
-        };
+            let l2geth_client = match network_config.l2geth {
+                Some(l2geth) => Some(Arc::new(L2gethClient::new(l2geth)?)),
+                None => None,
+            };
+            networks.push(StdRwLock::new(Arc::new(Network {
+                name,
+                proof_types,
+                l2geth_client,
+            })));
+        }
 
-        let db_path = self.cfg.db_path.unwrap_or_else(|| {
-            panic!("Missing database path");
-        });
+        let db_path = self
+            .cfg
+            .db_path
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("missing db_path"))?;
 
         Ok(Prover {
             circuit_type: self.cfg.prover.circuit_type,
-            proof_types: self.cfg.prover.supported_proof_types,
-            circuit_version: self.cfg.prover.circuit_version,
+            circuit_version: self.cfg.prover.circuit_version.clone(),
+            networks,
+            worker_networks,
             coordinator_clients,
-            l2geth_client,
-            proving_service: RwLock::new(self.proving_service),
-            n_workers: self.cfg.prover.n_workers,
-            health_listener_addr: self.cfg.health_listener_addr,
+            proving_service: self.proving_service,
+            n_workers: total_workers,
+            health_listener_addr: self.cfg.health_listener_addr.clone(),
+            task_history_endpoint: self.cfg.task_history_endpoint,
+            drain_timeout_sec: self.cfg.prover.drain_timeout_sec,
+            prefetch_depth: self.cfg.prover.prefetch_depth,
+            polling: StdRwLock::new(self.cfg.prover.polling.clone()),
+            max_transient_retries: self.cfg.prover.max_transient_retries,
+            task_deadlines: self.cfg.prover.deadlines.clone(),
+            input_config: self.cfg.prover.input.clone(),
+            cancel_on_shutdown: self.cfg.prover.cancel_on_shutdown,
+            task_slots: self
+                .cfg
+                .prover
+                .max_concurrent_tasks
+                .map(|n| Arc::new(Semaphore::new(n.max(1)))),
+            block_cache: self.cfg.block_cache.clone(),
             db: Db::new(&db_path)?,
+            shutdown: watch::channel(false).0,
+            worker_health: (0..total_workers).map(|_| Default::default()).collect(),
+            config: StdMutex::new(self.cfg),
+            config_source: self.config_source,
         })
     }
 }
@@ -0,0 +1,228 @@
+use super::{Prover, ProvingService};
+use axum::{extract::State, http::StatusCode, Json};
+use futures::future::join_all;
+use serde::Serialize;
+use std::sync::{
+    atomic::{AtomicU8, Ordering},
+    Arc,
+};
+use tokio::time::{timeout, Duration};
+
+const DEPENDENCY_CHECK_TIMEOUT_SEC: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerState {
+    Starting,
+    Running,
+    Stopped,
+}
+
+/// Tracks whether a worker loop is still running.
+pub struct WorkerHealth {
+    state: AtomicU8,
+}
+
+impl Default for WorkerHealth {
+    fn default() -> Self {
+        Self {
+            state: AtomicU8::new(WorkerState::Starting as u8),
+        }
+    }
+}
+
+impl WorkerHealth {
+    /// Marks the worker as running until the returned guard is dropped, which also happens when
+    /// the worker task panics or is aborted.
+    pub fn enter(&self) -> RunningGuard<'_> {
+        self.state
+            .store(WorkerState::Running as u8, Ordering::SeqCst);
+        RunningGuard(&self.state)
+    }
+
+    fn state(&self) -> WorkerState {
+        match self.state.load(Ordering::SeqCst) {
+            s if s == WorkerState::Starting as u8 => WorkerState::Starting,
+            s if s == WorkerState::Running as u8 => WorkerState::Running,
+            _ => WorkerState::Stopped,
+        }
+    }
+}
+
+pub struct RunningGuard<'a>(&'a AtomicU8);
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(WorkerState::Stopped as u8, Ordering::SeqCst);
+    }
+}
+
+#[derive(Serialize)]
+pub struct DependencyStatus {
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+impl From<Result<(), String>> for DependencyStatus {
+    fn from(result: Result<(), String>) -> Self {
+        match result {
+            Ok(()) => Self {
+                ok: true,
+                error: None,
+            },
+            Err(error) => Self {
+                ok: false,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct WorkerStatus {
+    pub index: usize,
+    pub network: String,
+    pub prover_name: String,
+    pub state: WorkerState,
+    pub coordinator: DependencyStatus,
+}
+
+#[derive(Serialize)]
+pub struct LivenessReport {
+    pub live: bool,
+    pub workers: Vec<WorkerStatus>,
+}
+
+#[derive(Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub shutting_down: bool,
+    pub l2geth: Option<DependencyStatus>,
+    pub proving_service: DependencyStatus,
+    pub workers: Vec<WorkerStatus>,
+}
+
+fn status_code(ok: bool) -> StatusCode {
+    if ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+impl<Backend> Prover<Backend>
+where
+    Backend: ProvingService + Send + Sync + 'static,
+{
+    fn worker_statuses(&self) -> Vec<WorkerStatus> {
+        self.worker_health
+            .iter()
+            .enumerate()
+            .map(|(index, health)| {
+                let coordinator_client = self.coordinator_client(index);
+                let coordinator = match coordinator_client.last_login_result() {
+                    Some(result) => result.into(),
+                    None => Err("not logged in yet".to_string()).into(),
+                };
+                WorkerStatus {
+                    index,
+                    network: self.network(index).name.clone(),
+                    prover_name: coordinator_client.prover_name.clone(),
+                    state: health.state(),
+                    coordinator,
+                }
+            })
+            .collect()
+    }
+
+    /// Checks the l2geth of every network that has one, failing if any of them is unreachable.
+    async fn check_l2geth(&self) -> Option<DependencyStatus> {
+        let networks: Vec<_> = self
+            .networks
+            .iter()
+            .map(|network| Arc::clone(&network.read().unwrap()))
+            .collect();
+        let checks = networks.iter().filter_map(|network| {
+            let l2geth_client = network.l2geth_client.as_ref()?;
+            Some(async move {
+                match timeout(
+                    Duration::from_secs(DEPENDENCY_CHECK_TIMEOUT_SEC),
+                    l2geth_client.block_number(),
+                )
+                .await
+                {
+                    Ok(Ok(_)) => None,
+                    Ok(Err(e)) => Some(format!("{}: {e}", network.name)),
+                    Err(_) => Some(format!("{}: timed out", network.name)),
+                }
+            })
+        });
+        let checks: Vec<_> = checks.collect();
+        if checks.is_empty() {
+            return None;
+        }
+        let errors: Vec<String> = join_all(checks).await.into_iter().flatten().collect();
+        let result = if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        };
+        Some(result.into())
+    }
+
+    async fn check_proving_service(&self) -> DependencyStatus {
+        match timeout(
+            Duration::from_secs(DEPENDENCY_CHECK_TIMEOUT_SEC),
+            self.proving_service.health_check(),
+        )
+        .await
+        {
+            Ok(result) => result.into(),
+            Err(_) => Err("timed out".to_string()).into(),
+        }
+    }
+}
+
+/// Reports whether every worker loop is still running, or has yet to start. Workers stop one by
+/// one while draining, so during shutdown the process is reported live as long as it serves this.
+pub async fn livez<Backend>(
+    State(prover): State<Arc<Prover<Backend>>>,
+) -> (StatusCode, Json<LivenessReport>)
+where
+    Backend: ProvingService + Send + Sync + 'static,
+{
+    let workers = prover.worker_statuses();
+    let live = prover.is_shutting_down() || workers.iter().all(|w| w.state != WorkerState::Stopped);
+    (status_code(live), Json(LivenessReport { live, workers }))
+}
+
+/// Reports whether the prover can currently do work: every worker is running and logged in to
+/// the coordinator, and l2geth and the proving service are reachable.
+pub async fn readyz<Backend>(
+    State(prover): State<Arc<Prover<Backend>>>,
+) -> (StatusCode, Json<ReadinessReport>)
+where
+    Backend: ProvingService + Send + Sync + 'static,
+{
+    let shutting_down = prover.is_shutting_down();
+    let workers = prover.worker_statuses();
+    let (l2geth, proving_service) =
+        tokio::join!(prover.check_l2geth(), prover.check_proving_service());
+
+    let ready = !shutting_down
+        && workers
+            .iter()
+            .all(|w| w.state == WorkerState::Running && w.coordinator.ok)
+        && l2geth.as_ref().is_none_or(|s| s.ok)
+        && proving_service.ok;
+    (
+        status_code(ready),
+        Json(ReadinessReport {
+            ready,
+            shutting_down,
+            l2geth,
+            proving_service,
+            workers,
+        }),
+    )
+}
@@ -0,0 +1,19 @@
+use super::{Prover, ProvingService};
+use crate::db::{TaskHistoryFilter, TaskHistoryPage};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use std::sync::Arc;
+
+/// Lists the history of tasks handled by this prover, newest first, filtered and paged by the
+/// query parameters of [`TaskHistoryFilter`]. Only served if `task_history_endpoint` is set.
+pub async fn task_history<Backend>(
+    State(prover): State<Arc<Prover<Backend>>>,
+    Query(filter): Query<TaskHistoryFilter>,
+) -> Json<TaskHistoryPage>
+where
+    Backend: ProvingService + Send + Sync + 'static,
+{
+    Json(prover.db.list_task_history(&filter))
+}
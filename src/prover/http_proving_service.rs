@@ -0,0 +1,551 @@
+//! A [`ProvingService`] backed by a remote proving service over HTTP.
+//!
+//! Every request carries the API key as `Authorization: Bearer <api_key>`, and every body other
+//! than an uploaded input is JSON. Proof types are encoded as their coordinator `u8` values and
+//! timestamps as Unix seconds.
+//!
+//! | Method | Path                         | Request body          | Response body     |
+//! |--------|------------------------------|-----------------------|-------------------|
+//! | `POST` | `/v1/vks`                    | [`VksRequest`]        | [`VksResponse`]   |
+//! | `POST` | `/v1/inputs`                 | the raw input         | [`InputResponse`] |
+//! | `POST` | `/v1/tasks`                  | [`CreateTaskRequest`] | [`TaskResponse`]  |
+//! | `GET`  | `/v1/tasks/{task_id}`        |                       | [`TaskResponse`]  |
+//! | `POST` | `/v1/tasks/{task_id}/cancel` |                       | [`TaskResponse`]  |
+//! | `GET`  | `/v1/health`                 |                       |                   |
+//!
+//! Inputs are streamed into the `input` of [`CreateTaskRequest`] unless `upload_inputs` is set, in
+//! which case each is first streamed to `/v1/inputs`, with `Content-Encoding` set if it is
+//! compressed, and the task refers to it by `input_id`. The gzip or zstd compression of
+//! `input.compression` only applies to uploads: embedded inputs are sent decompressed, as JSON
+//! strings.
+//!
+//! Any status other than `200 OK` is treated as a failure, and its body should be an
+//! [`ErrorResponse`]. Failed tasks should set `failure_kind` so the SDK can tell transient
+//! infrastructure errors from circuit panics. Prove requests that cannot reach the service, or are
+//! answered with `429` or a `5xx` status, are classified as transient, and those answered with
+//! `400` or `422` as invalid input. A status query answered with `404` reports the task as failed
+//! with a transient error, and any other failed query reports its status as unknown.
+//! Services that cannot cancel tasks should answer cancel requests with `501`.
+
+use super::{
+    proving_service::{
+        CancelTaskError, CancelTaskRequest, FailureKind, GetVkRequest, GetVkResponse, ProveRequest,
+        ProveResponse, QueryTaskRequest, QueryTaskResponse, TaskStatus,
+    },
+    ProofType, ProveInput, ProvingService,
+};
+use crate::config::ProvingServiceConfig;
+use async_trait::async_trait;
+use bytes::Bytes;
+use core::time::Duration;
+use futures::{stream, StreamExt, TryStreamExt};
+use reqwest::{
+    header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE},
+    Body, Method, StatusCode, Url,
+};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VksRequest {
+    pub proof_types: Vec<ProofType>,
+    pub circuit_version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VksResponse {
+    pub vks: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateTaskRequest {
+    pub proof_type: ProofType,
+    pub circuit_version: String,
+    pub hard_fork_name: String,
+    /// Empty when the input was uploaded.
+    #[serde(default)]
+    pub input: String,
+    /// The id of an uploaded input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputResponse {
+    pub input_id: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskResponse {
+    pub task_id: String,
+    pub proof_type: ProofType,
+    pub circuit_version: String,
+    pub hard_fork_name: String,
+    pub status: TaskStatus,
+    pub created_at: f64,
+    #[serde(default)]
+    pub started_at: Option<f64>,
+    #[serde(default)]
+    pub finished_at: Option<f64>,
+    #[serde(default)]
+    pub compute_time_sec: Option<f64>,
+    #[serde(default)]
+    pub remaining_time_sec: Option<f64>,
+    #[serde(default)]
+    pub input: Option<String>,
+    #[serde(default)]
+    pub proof: Option<String>,
+    #[serde(default)]
+    pub vk: Option<String>,
+    /// Set when `status` is `failed`.
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub failure_kind: Option<FailureKind>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+/// A non-`200 OK` answer from the proving service.
+#[derive(Debug)]
+struct StatusError {
+    path: String,
+    status: StatusCode,
+    error: String,
+}
+
+impl std::fmt::Display for StatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[proving service client], {}, status {}: {}",
+            self.path, self.status, self.error
+        )
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+/// Classifies a failed request. Errors without a status never reached the service.
+fn failure_kind_of(error: &anyhow::Error) -> Option<FailureKind> {
+    let Some(error) = error.downcast_ref::<StatusError>() else {
+        return (error.is::<reqwest_middleware::Error>() || error.is::<reqwest::Error>())
+            .then_some(FailureKind::Transient);
+    };
+    match error.status {
+        StatusCode::TOO_MANY_REQUESTS => Some(FailureKind::Transient),
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+            Some(FailureKind::InvalidInput)
+        }
+        status if status.is_server_error() => Some(FailureKind::Transient),
+        _ => None,
+    }
+}
+
+/// Escapes a chunk of a JSON string the way `serde_json` does. Only ASCII bytes are escaped, so
+/// UTF-8 sequences split across chunks pass through intact.
+fn escape_json_chunk(chunk: Bytes) -> Bytes {
+    if !chunk.iter().any(|&b| b == b'"' || b == b'\\' || b < 0x20) {
+        return chunk;
+    }
+    let mut escaped = Vec::with_capacity(chunk.len() + chunk.len() / 8);
+    for &b in chunk.iter() {
+        match b {
+            b'"' => escaped.extend_from_slice(b"\\\""),
+            b'\\' => escaped.extend_from_slice(b"\\\\"),
+            b'\n' => escaped.extend_from_slice(b"\\n"),
+            b'\r' => escaped.extend_from_slice(b"\\r"),
+            b'\t' => escaped.extend_from_slice(b"\\t"),
+            b if b < 0x20 => escaped.extend_from_slice(format!("\\u{b:04x}").as_bytes()),
+            b => escaped.push(b),
+        }
+    }
+    escaped.into()
+}
+
+impl From<TaskResponse> for ProveResponse {
+    fn from(task: TaskResponse) -> Self {
+        Self {
+            task_id: task.task_id,
+            proof_type: task.proof_type,
+            circuit_version: task.circuit_version,
+            hard_fork_name: task.hard_fork_name,
+            status: task.status,
+            created_at: task.created_at,
+            started_at: task.started_at,
+            finished_at: task.finished_at,
+            compute_time_sec: task.compute_time_sec,
+            input: task.input,
+            proof: task.proof,
+            vk: task.vk,
+            error: task.error,
+            failure_kind: task.failure_kind,
+        }
+    }
+}
+
+impl From<TaskResponse> for QueryTaskResponse {
+    fn from(task: TaskResponse) -> Self {
+        Self {
+            task_id: task.task_id,
+            proof_type: task.proof_type,
+            circuit_version: task.circuit_version,
+            hard_fork_name: task.hard_fork_name,
+            status: task.status,
+            created_at: task.created_at,
+            started_at: task.started_at,
+            finished_at: task.finished_at,
+            compute_time_sec: task.compute_time_sec,
+            remaining_time_sec: task.remaining_time_sec,
+            input: task.input,
+            proof: task.proof,
+            vk: task.vk,
+            error: task.error,
+            failure_kind: task.failure_kind,
+        }
+    }
+}
+
+pub struct HttpProvingService {
+    base_url: Url,
+    api_key: String,
+    send_timeout: Duration,
+    client: ClientWithMiddleware,
+    /// Without the retry middleware, which cannot retry streamed bodies.
+    upload_client: reqwest::Client,
+    upload_inputs: bool,
+    retry_count: u32,
+    retry_wait_duration: Duration,
+}
+
+impl HttpProvingService {
+    pub fn new(cfg: ProvingServiceConfig) -> anyhow::Result<Self> {
+        let retry_wait_duration = Duration::from_secs(cfg.retry_wait_time_sec);
+        let retry_policy = ExponentialBackoff::builder()
+            .retry_bounds(retry_wait_duration / 2, retry_wait_duration)
+            .build_with_max_retries(cfg.retry_count);
+
+        let client = ClientBuilder::new(reqwest::Client::new())
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .build();
+
+        let send_timeout = Duration::from_secs(cfg.connection_timeout_sec);
+        // Uploads take as long as the input takes to stream, so only connecting is bounded.
+        let upload_client = reqwest::Client::builder()
+            .connect_timeout(send_timeout)
+            .build()?;
+
+        Ok(Self {
+            base_url: Url::parse(&cfg.base_url)?,
+            api_key: cfg.api_key,
+            send_timeout,
+            client,
+            upload_client,
+            upload_inputs: cfg.upload_inputs,
+            retry_count: cfg.retry_count,
+            retry_wait_duration,
+        })
+    }
+
+    fn build_url(&self, path: &str) -> anyhow::Result<Url> {
+        self.base_url.join(path).map_err(|e| anyhow::anyhow!(e))
+    }
+
+    fn task_url(&self, task_id: &str) -> anyhow::Result<Url> {
+        let mut url = self.build_url("/v1/tasks")?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("base_url cannot be a base"))?
+            .push(task_id);
+        Ok(url)
+    }
+
+    /// Sends a request and returns the body of a `200 OK` response.
+    async fn send<Req>(&self, method: Method, url: Url, req: Option<&Req>) -> anyhow::Result<String>
+    where
+        Req: Serialize,
+    {
+        let path = url.path().to_string();
+        let mut request = self
+            .client
+            .request(method, url)
+            .bearer_auth(&self.api_key)
+            .timeout(self.send_timeout);
+        if let Some(req) = req {
+            request = request
+                .header(CONTENT_TYPE, "application/json")
+                .body(serde_json::to_string(req)?);
+        }
+
+        log::info!("[proving service client], {path}, sent request");
+        let response = request.send().await?;
+        Self::read_response(path, response).await
+    }
+
+    async fn read_response(path: String, response: reqwest::Response) -> anyhow::Result<String> {
+        let status = response.status();
+        let response_body = response.text().await?;
+        log::info!("[proving service client], {path}, received response");
+        log::debug!("[proving service client], {path}, response: {response_body}");
+
+        if status != StatusCode::OK {
+            let error = serde_json::from_str::<ErrorResponse>(&response_body)
+                .map(|e| e.error)
+                .unwrap_or(response_body);
+            return Err(StatusError {
+                path,
+                status,
+                error,
+            }
+            .into());
+        }
+        Ok(response_body)
+    }
+
+    async fn send_json<Req, Resp>(
+        &self,
+        method: Method,
+        url: Url,
+        req: Option<&Req>,
+    ) -> anyhow::Result<Resp>
+    where
+        Req: Serialize,
+        Resp: DeserializeOwned,
+    {
+        let response_body = self.send(method, url, req).await?;
+        serde_json::from_str(&response_body).map_err(|e| anyhow::anyhow!(e))
+    }
+
+    async fn fetch_vks(&self, req: &VksRequest) -> anyhow::Result<VksResponse> {
+        let url = self.build_url("/v1/vks")?;
+        self.send_json(Method::POST, url, Some(req)).await
+    }
+
+    /// Retries `f` on transient failures. Used for streamed bodies, which the retry middleware
+    /// cannot retry.
+    async fn retry_transient<T, F, Fut>(&self, path: &str, f: F) -> anyhow::Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match f().await {
+                Err(e)
+                    if attempt < self.retry_count
+                        && failure_kind_of(&e) == Some(FailureKind::Transient) =>
+                {
+                    attempt += 1;
+                    log::warn!(
+                        "[proving service client], {path}, retrying request, attempt {attempt}: {e}"
+                    );
+                    tokio::time::sleep(self.retry_wait_duration).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Streams an input to the proving service.
+    async fn upload_input(&self, input: &ProveInput) -> anyhow::Result<InputResponse> {
+        let url = self.build_url("/v1/inputs")?;
+        self.retry_transient(url.path(), || self.try_upload_input(url.clone(), input))
+            .await
+    }
+
+    async fn try_upload_input(
+        &self,
+        url: Url,
+        input: &ProveInput,
+    ) -> anyhow::Result<InputResponse> {
+        let path = url.path().to_string();
+        let body = Body::wrap_stream(tokio_util::io::ReaderStream::new(input.reader().await?));
+        let mut request = self
+            .upload_client
+            .post(url)
+            .bearer_auth(&self.api_key)
+            .header(CONTENT_TYPE, "application/json")
+            .header(CONTENT_LENGTH, input.len())
+            .body(body);
+        if let Some(encoding) = input.compression().content_encoding() {
+            request = request.header(CONTENT_ENCODING, encoding);
+        }
+
+        log::info!(
+            "[proving service client], {path}, uploading {} bytes",
+            input.len()
+        );
+        let response = request.send().await?;
+        let response_body = Self::read_response(path, response).await?;
+        serde_json::from_str(&response_body).map_err(|e| anyhow::anyhow!(e))
+    }
+
+    /// Creates a task for `req`, uploading its input first or embedding it, as configured.
+    async fn create_task(&self, req: &ProveRequest) -> anyhow::Result<TaskResponse> {
+        let url = self.build_url("/v1/tasks")?;
+        if !self.upload_inputs {
+            return self
+                .retry_transient(url.path(), || {
+                    self.try_create_task_embedded(url.clone(), req)
+                })
+                .await;
+        }
+        let create_req = CreateTaskRequest {
+            proof_type: req.proof_type,
+            circuit_version: req.circuit_version.clone(),
+            hard_fork_name: req.hard_fork_name.clone(),
+            input: String::new(),
+            input_id: Some(self.upload_input(&req.input).await?.input_id),
+        };
+        self.send_json(Method::POST, url, Some(&create_req)).await
+    }
+
+    /// Sends a [`CreateTaskRequest`] whose `input` is streamed from the decoded input, so the
+    /// input is never held in memory as a whole.
+    async fn try_create_task_embedded(
+        &self,
+        url: Url,
+        req: &ProveRequest,
+    ) -> anyhow::Result<TaskResponse> {
+        let path = url.path().to_string();
+        let mut head = serde_json::to_value(CreateTaskRequest {
+            proof_type: req.proof_type,
+            circuit_version: req.circuit_version.clone(),
+            hard_fork_name: req.hard_fork_name.clone(),
+            input: String::new(),
+            input_id: None,
+        })?;
+        head.as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("CreateTaskRequest is not a JSON object"))?
+            .remove("input");
+        let mut head = serde_json::to_string(&head)?;
+        // Reopen the object and append `"input":"`, leaving the string open for the chunks.
+        head.pop();
+        if !head.ends_with('{') {
+            head.push(',');
+        }
+        head.push_str("\"input\":\"");
+
+        let body = stream::once(async move { Ok(Bytes::from(head)) })
+            .chain(req.input.decoded_stream().map_ok(escape_json_chunk))
+            .chain(stream::once(async { Ok(Bytes::from_static(b"\"}")) }));
+        let request = self
+            .upload_client
+            .post(url)
+            .bearer_auth(&self.api_key)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::wrap_stream(body));
+
+        log::info!("[proving service client], {path}, sent request");
+        let response = request.send().await?;
+        let response_body = Self::read_response(path, response).await?;
+        serde_json::from_str(&response_body).map_err(|e| anyhow::anyhow!(e))
+    }
+
+    async fn fetch_task(&self, task_id: &str) -> anyhow::Result<TaskResponse> {
+        let url = self.task_url(task_id)?;
+        self.send_json::<(), _>(Method::GET, url, None).await
+    }
+
+    async fn post_cancel_task(&self, task_id: &str) -> anyhow::Result<TaskResponse> {
+        let mut url = self.task_url(task_id)?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("base_url cannot be a base"))?
+            .push("cancel");
+        self.send_json::<(), _>(Method::POST, url, None).await
+    }
+}
+
+#[async_trait]
+impl ProvingService for HttpProvingService {
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    async fn get_vks(&self, req: GetVkRequest) -> GetVkResponse {
+        let req = VksRequest {
+            proof_types: req.proof_types,
+            circuit_version: req.circuit_version,
+        };
+        match self.fetch_vks(&req).await {
+            Ok(resp) => GetVkResponse {
+                vks: resp.vks,
+                error: None,
+            },
+            Err(e) => GetVkResponse {
+                error: Some(e.to_string()),
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn prove(&self, req: ProveRequest) -> ProveResponse {
+        match self.create_task(&req).await {
+            Ok(task) => task.into(),
+            Err(e) => ProveResponse {
+                proof_type: req.proof_type,
+                circuit_version: req.circuit_version,
+                hard_fork_name: req.hard_fork_name,
+                status: TaskStatus::Failed,
+                error: Some(e.to_string()),
+                failure_kind: failure_kind_of(&e),
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn query_task(&self, req: QueryTaskRequest) -> QueryTaskResponse {
+        match self.fetch_task(&req.task_id).await {
+            Ok(mut task) => {
+                if task.status == TaskStatus::Failed && task.error.is_none() {
+                    task.error = Some("proving service reported no error message".to_string());
+                }
+                task.into()
+            }
+            // The proving service lost the task, e.g. on a restart, so it has to be proved again.
+            Err(e)
+                if e.downcast_ref::<StatusError>()
+                    .is_some_and(|e| e.status == StatusCode::NOT_FOUND) =>
+            {
+                QueryTaskResponse {
+                    task_id: req.task_id,
+                    status: TaskStatus::Failed,
+                    error: Some(e.to_string()),
+                    failure_kind: Some(FailureKind::Transient),
+                    ..Default::default()
+                }
+            }
+            // Any other failed status query says nothing about the task itself.
+            Err(e) => QueryTaskResponse {
+                task_id: req.task_id,
+                status: TaskStatus::Unknown,
+                error: Some(e.to_string()),
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn cancel_task(&self, req: CancelTaskRequest) -> Result<(), CancelTaskError> {
+        match self.post_cancel_task(&req.task_id).await {
+            Ok(_) => Ok(()),
+            Err(e)
+                if e.downcast_ref::<StatusError>()
+                    .is_some_and(|e| e.status == StatusCode::NOT_IMPLEMENTED) =>
+            {
+                Err(CancelTaskError::Unsupported)
+            }
+            Err(e) => Err(CancelTaskError::Failed(e.to_string())),
+        }
+    }
+
+    async fn health_check(&self) -> Result<(), String> {
+        let url = self.build_url("/v1/health").map_err(|e| e.to_string())?;
+        self.send::<()>(Method::GET, url, None)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
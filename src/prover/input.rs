@@ -0,0 +1,383 @@
+//! Proving inputs, which for chunks can be hundreds of MB and are therefore not held as strings.
+
+use crate::config::InputConfig;
+use bytes::Bytes;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    io::{self, BufWriter, Read, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+use tempfile::NamedTempFile;
+use tokio::{io::AsyncRead, sync::mpsc};
+
+/// Size of the chunks read by [`ProveInput::decoded_stream`].
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Compression applied to a [`ProveInput`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputCompression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl InputCompression {
+    /// Value of the HTTP `Content-Encoding` header for inputs compressed this way.
+    pub fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            InputCompression::None => None,
+            InputCompression::Gzip => Some("gzip"),
+            InputCompression::Zstd => Some("zstd"),
+        }
+    }
+
+    /// Wraps `reader` so that it yields the decompressed bytes.
+    pub fn decoder<'a, R: Read + Send + 'a>(
+        self,
+        reader: R,
+    ) -> io::Result<Box<dyn Read + Send + 'a>> {
+        Ok(match self {
+            InputCompression::None => Box::new(reader),
+            InputCompression::Gzip => Box::new(flate2::read::GzDecoder::new(reader)),
+            InputCompression::Zstd => Box::new(zstd::Decoder::new(reader)?),
+        })
+    }
+}
+
+#[derive(Clone)]
+enum InputData {
+    Bytes(Bytes),
+    /// Removed once the last clone of the input is dropped.
+    File(Arc<NamedTempFile>),
+}
+
+/// The input of a [`ProveRequest`](super::proving_service::ProveRequest), held in memory or
+/// spooled to a temp file, and compressed if configured.
+///
+/// Cloning is cheap, and the input can be read any number of times so that failed uploads can be
+/// retried. Backends that upload the input should stream it with [`reader`](Self::reader);
+/// local backends can read it decompressed with [`decoded_reader`](Self::decoded_reader).
+#[derive(Clone)]
+pub struct ProveInput {
+    data: InputData,
+    compression: InputCompression,
+    len: u64,
+}
+
+impl ProveInput {
+    pub fn from_bytes(bytes: impl Into<Bytes>) -> Self {
+        let bytes = bytes.into();
+        Self {
+            len: bytes.len() as u64,
+            data: InputData::Bytes(bytes),
+            compression: InputCompression::None,
+        }
+    }
+
+    pub fn compression(&self) -> InputCompression {
+        self.compression
+    }
+
+    /// Size of the input as stored, i.e. after compression.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the input was spooled to a temp file.
+    pub fn is_spooled(&self) -> bool {
+        matches!(self.data, InputData::File(_))
+    }
+
+    /// Streams the input as stored, compressed if it is.
+    pub async fn reader(&self) -> io::Result<Box<dyn AsyncRead + Send + Unpin>> {
+        Ok(match &self.data {
+            InputData::Bytes(bytes) => Box::new(io::Cursor::new(bytes.clone())),
+            InputData::File(file) => Box::new(tokio::fs::File::open(file.path()).await?),
+        })
+    }
+
+    /// Reads the input decompressed. Reads block, so use it off the async runtime for large
+    /// inputs.
+    pub fn decoded_reader(&self) -> io::Result<Box<dyn Read + Send>> {
+        let reader: Box<dyn Read + Send> = match &self.data {
+            InputData::Bytes(bytes) => Box::new(io::Cursor::new(bytes.clone())),
+            InputData::File(file) => Box::new(io::BufReader::new(file.reopen()?)),
+        };
+        self.compression.decoder(reader)
+    }
+
+    /// Streams the input decompressed, reading it on a blocking thread.
+    pub fn decoded_stream(&self) -> impl Stream<Item = io::Result<Bytes>> + Send + 'static {
+        let (tx, rx) = mpsc::channel(4);
+        let input = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut reader = match input.decoded_reader() {
+                Ok(reader) => reader,
+                Err(e) => {
+                    let _ = tx.blocking_send(Err(e));
+                    return;
+                }
+            };
+            loop {
+                let mut chunk = vec![0; STREAM_CHUNK_SIZE];
+                let chunk = match reader.read(&mut chunk) {
+                    Ok(0) => return,
+                    Ok(n) => {
+                        chunk.truncate(n);
+                        Ok(Bytes::from(chunk))
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => Err(e),
+                };
+                let failed = chunk.is_err();
+                // Stops reading once the stream is dropped.
+                if tx.blocking_send(chunk).is_err() || failed {
+                    return;
+                }
+            }
+        });
+        futures::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|chunk| (chunk, rx))
+        })
+    }
+
+    /// Reads the whole input decompressed into a string, for backends that need one.
+    pub fn read_to_string(&self) -> io::Result<String> {
+        let mut input = String::new();
+        self.decoded_reader()?.read_to_string(&mut input)?;
+        Ok(input)
+    }
+}
+
+impl Default for ProveInput {
+    fn default() -> Self {
+        Self::from_bytes(Bytes::new())
+    }
+}
+
+impl From<String> for ProveInput {
+    fn from(input: String) -> Self {
+        Self::from_bytes(input)
+    }
+}
+
+impl From<&str> for ProveInput {
+    fn from(input: &str) -> Self {
+        Self::from_bytes(input.to_string())
+    }
+}
+
+impl fmt::Debug for ProveInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProveInput")
+            .field("len", &self.len)
+            .field("compression", &self.compression)
+            .field("spooled", &self.is_spooled())
+            .finish()
+    }
+}
+
+/// Keeps written bytes in memory until `threshold` is reached, then moves them to a temp file.
+struct Spool {
+    buffer: Vec<u8>,
+    file: Option<BufWriter<NamedTempFile>>,
+    threshold: u64,
+    dir: Option<PathBuf>,
+    len: u64,
+}
+
+impl Spool {
+    fn spill(&mut self) -> io::Result<()> {
+        let file = match &self.dir {
+            Some(dir) => NamedTempFile::new_in(dir)?,
+            None => NamedTempFile::new()?,
+        };
+        let mut file = BufWriter::new(file);
+        file.write_all(&self.buffer)?;
+        self.buffer = Vec::new();
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn finish(self) -> io::Result<(InputData, u64)> {
+        let data = match self.file {
+            Some(file) => {
+                let file = file.into_inner().map_err(|e| e.into_error())?;
+                file.as_file().sync_data()?;
+                InputData::File(Arc::new(file))
+            }
+            None => InputData::Bytes(self.buffer.into()),
+        };
+        Ok((data, self.len))
+    }
+}
+
+impl Write for Spool {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.file.is_none() && self.len + buf.len() as u64 > self.threshold {
+            self.spill()?;
+        }
+        match &mut self.file {
+            Some(file) => file.write_all(buf)?,
+            None => self.buffer.extend_from_slice(buf),
+        }
+        self.len += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+enum Encoder {
+    None(Spool),
+    Gzip(flate2::write::GzEncoder<Spool>),
+    Zstd(zstd::Encoder<'static, Spool>),
+}
+
+/// Builds a [`ProveInput`] piece by piece, compressing and spooling it as configured, so that the
+/// whole input is never held in memory at once.
+pub struct ProveInputWriter {
+    encoder: Encoder,
+    compression: InputCompression,
+}
+
+impl ProveInputWriter {
+    pub fn new(cfg: &InputConfig) -> io::Result<Self> {
+        let spool = Spool {
+            buffer: Vec::new(),
+            file: None,
+            threshold: cfg.spool_threshold_bytes,
+            dir: cfg.spool_dir.as_ref().map(PathBuf::from),
+            len: 0,
+        };
+        let encoder = match cfg.compression {
+            InputCompression::None => Encoder::None(spool),
+            InputCompression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                spool,
+                flate2::Compression::default(),
+            )),
+            InputCompression::Zstd => Encoder::Zstd(zstd::Encoder::new(spool, 0)?),
+        };
+        Ok(Self {
+            encoder,
+            compression: cfg.compression,
+        })
+    }
+
+    /// Runs `write` with this writer on a blocking thread, since compressing and spooling the
+    /// written bytes may block.
+    pub async fn write_blocking<F>(mut self, write: F) -> io::Result<Self>
+    where
+        F: FnOnce(&mut Self) -> io::Result<()> + Send + 'static,
+    {
+        tokio::task::spawn_blocking(move || write(&mut self).map(|_| self))
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    /// Like [`finish`](Self::finish), on a blocking thread.
+    pub async fn finish_blocking(self) -> io::Result<ProveInput> {
+        tokio::task::spawn_blocking(move || self.finish())
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    pub fn finish(self) -> io::Result<ProveInput> {
+        let spool = match self.encoder {
+            Encoder::None(spool) => spool,
+            Encoder::Gzip(encoder) => encoder.finish()?,
+            Encoder::Zstd(encoder) => encoder.finish()?,
+        };
+        let (data, len) = spool.finish()?;
+        Ok(ProveInput {
+            data,
+            compression: self.compression,
+            len,
+        })
+    }
+}
+
+impl Write for ProveInputWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.encoder {
+            Encoder::None(spool) => spool.write(buf),
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            Encoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.encoder {
+            Encoder::None(spool) => spool.flush(),
+            Encoder::Gzip(encoder) => encoder.flush(),
+            Encoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_prove_input_round_trip() {
+        let content = "trace,".repeat(1000);
+        for compression in [
+            InputCompression::None,
+            InputCompression::Gzip,
+            InputCompression::Zstd,
+        ] {
+            for spool_threshold_bytes in [0, 1 << 20] {
+                let cfg = InputConfig {
+                    compression,
+                    spool_threshold_bytes,
+                    spool_dir: None,
+                };
+                let mut writer = ProveInputWriter::new(&cfg).unwrap();
+                for chunk in content.as_bytes().chunks(100) {
+                    writer.write_all(chunk).unwrap();
+                }
+                let input = writer.finish().unwrap();
+                assert_eq!(input.is_spooled(), spool_threshold_bytes == 0);
+                assert_eq!(input.compression(), compression);
+                assert_eq!(input.read_to_string().unwrap(), content);
+
+                let mut stored = vec![];
+                input
+                    .reader()
+                    .await
+                    .unwrap()
+                    .read_to_end(&mut stored)
+                    .await
+                    .unwrap();
+                assert_eq!(stored.len() as u64, input.len());
+                let mut decoded = String::new();
+                compression
+                    .decoder(stored.as_slice())
+                    .unwrap()
+                    .read_to_string(&mut decoded)
+                    .unwrap();
+                assert_eq!(decoded, content);
+
+                let streamed: Vec<Bytes> = input.decoded_stream().try_collect().await.unwrap();
+                assert_eq!(streamed.concat(), content.as_bytes());
+            }
+        }
+    }
+}
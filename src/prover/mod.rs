@@ -1,39 +1,89 @@
 pub mod builder;
+mod health;
+mod history;
+pub mod http_proving_service;
+pub mod input;
+mod polling;
 pub mod proving_service;
+mod reload;
 pub mod types;
 use crate::{
+    config::{BlockCacheConfig, Config, InputConfig, PollingConfig, TaskDeadlinesConfig},
     coordinator_handler::{
         ChunkTaskDetail, CoordinatorClient, ErrorCode, GetTaskRequest, GetTaskResponseData,
         ProofFailureType, ProofStatus, SubmitProofRequest,
     },
-    db::Db,
+    db::{Db, TaskRecordOutcome},
+    metrics,
     tracing_handler::L2gethClient,
 };
 use axum::{routing::get, Router};
 use ethers_core::types::H256;
 use ethers_providers::Middleware;
-use proving_service::{ProveRequest, QueryTaskRequest, TaskStatus};
+use futures::{stream, StreamExt, TryStreamExt};
+use health::WorkerHealth;
+use polling::Backoff;
+use proving_service::{
+    CancelTaskError, CancelTaskRequest, FailureKind, ProveRequest, QueryTaskRequest, TaskStatus,
+};
+use std::io::Write;
 use std::net::SocketAddr;
 use std::str::FromStr;
-use std::thread;
-use tokio::time::{sleep, Duration};
-use tokio::{sync::RwLock, task::JoinSet};
-use tracing::{error, info, instrument};
+use std::sync::{Arc, Mutex as StdMutex, RwLock as StdRwLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::time::{sleep, timeout, Duration};
+use tokio::{
+    sync::{mpsc, oneshot, watch, OwnedSemaphorePermit, Semaphore},
+    task::JoinSet,
+};
+use tracing::{error, info, instrument, warn};
 
-pub use {builder::ProverBuilder, proving_service::ProvingService, types::*};
+use reload::ConfigSource;
+
+pub use {
+    builder::ProverBuilder,
+    http_proving_service::HttpProvingService,
+    input::{InputCompression, ProveInput, ProveInputWriter},
+    proving_service::{ExclusiveProvingService, ProvingService, ProvingServiceMut},
+    types::*,
+};
 
-const WORKER_SLEEP_SEC: u64 = 20;
+/// A coordinator network served by some of the workers.
+struct Network {
+    name: String,
+    proof_types: Vec<ProofType>,
+    l2geth_client: Option<Arc<L2gethClient>>,
+}
 
 pub struct Prover<Backend: ProvingService + Send + Sync + 'static> {
     circuit_type: CircuitType,
-    proof_types: Vec<ProofType>,
     circuit_version: String,
-    coordinator_clients: Vec<CoordinatorClient>,
-    l2geth_client: Option<L2gethClient>,
-    proving_service: RwLock<Backend>,
+    /// Replaced on config reload, see `reload`. Workers pick up the new value between tasks.
+    networks: Vec<StdRwLock<Arc<Network>>>,
+    /// Index in `networks` of the network served by each worker.
+    worker_networks: Vec<usize>,
+    /// Replaced on config reload, like `networks`.
+    coordinator_clients: Vec<StdRwLock<Arc<CoordinatorClient>>>,
+    proving_service: Backend,
     n_workers: usize,
     health_listener_addr: String,
+    task_history_endpoint: bool,
+    drain_timeout_sec: u64,
+    prefetch_depth: usize,
+    polling: StdRwLock<PollingConfig>,
+    max_transient_retries: u32,
+    task_deadlines: TaskDeadlinesConfig,
+    input_config: InputConfig,
+    cancel_on_shutdown: bool,
+    /// Limits the tasks handled at once across all workers, if `max_concurrent_tasks` is set.
+    task_slots: Option<Arc<Semaphore>>,
+    block_cache: BlockCacheConfig,
     db: Db,
+    shutdown: watch::Sender<bool>,
+    worker_health: Vec<WorkerHealth>,
+    /// The config currently applied, and the files it is reloaded from if any.
+    config: StdMutex<Config>,
+    config_source: Option<ConfigSource>,
 }
 
 impl<Backend> Prover<Backend>
@@ -41,91 +91,500 @@ where
     Backend: ProvingService + Send + Sync + 'static,
 {
     pub async fn run(self) {
-        assert!(self.n_workers == self.coordinator_clients.len());
-        if self.proof_types.contains(&ProofType::Chunk) {
-            assert!(self.l2geth_client.is_some());
-        }
+        // Installed first so that a signal received while the workers start up is not missed.
+        self.run_until(shutdown_signal()).await
+    }
 
+    /// Like `run`, but shuts down and drains once `shutdown` resolves instead of on a signal.
+    pub async fn run_until(self, shutdown: impl std::future::Future<Output = ()>) {
+        let mut shutdown_signal = std::pin::pin!(shutdown);
         self.test_coordinator_connection().await;
 
-        let app = Router::new().route("/", get(|| async { "OK" }));
-        let addr = SocketAddr::from_str(&self.health_listener_addr)
+        let self_arc = std::sync::Arc::new(self);
+
+        let mut app = Router::new()
+            .route("/", get(|| async { "OK" }))
+            .route("/livez", get(health::livez::<Backend>))
+            .route("/readyz", get(health::readyz::<Backend>))
+            .route("/metrics", get(metrics::metrics_handler));
+        if self_arc.task_history_endpoint {
+            app = app.route("/tasks", get(history::task_history::<Backend>));
+        }
+        let app = app.with_state(std::sync::Arc::clone(&self_arc));
+        let addr = SocketAddr::from_str(&self_arc.health_listener_addr)
             .expect("Failed to parse socket address");
-        let server = axum::Server::bind(&addr).serve(app.into_make_service());
-        let health_check_server_task = tokio::spawn(server);
+        // Stopped only once the workers are drained, so that the endpoints report the shutdown.
+        let (health_shutdown, health_shutdown_rx) = oneshot::channel::<()>();
+        let server = axum::Server::bind(&addr)
+            .serve(app.into_make_service())
+            .with_graceful_shutdown(async move {
+                let _ = health_shutdown_rx.await;
+            });
+        let mut health_check_server_task = tokio::spawn(server);
+
+        if self_arc.block_cache.enabled {
+            let self_clone = std::sync::Arc::clone(&self_arc);
+            tokio::spawn(async move { self_clone.block_cache_eviction_loop().await });
+        }
+        if self_arc.config_source.is_some() {
+            let self_clone = std::sync::Arc::clone(&self_arc);
+            tokio::spawn(async move { self_clone.config_reload_loop().await });
+        }
 
         let mut provers = JoinSet::new();
-        let self_arc = std::sync::Arc::new(self);
+        let mut signal_received = false;
         for i in 0..self_arc.n_workers {
             let self_clone = std::sync::Arc::clone(&self_arc);
             provers.spawn(async move {
-                self_clone.working_loop(i).await;
+                let _running = self_clone.worker_health[i].enter();
+                let work = async {
+                    if self_clone.prefetch_depth > 0 {
+                        self_clone.pipelined_working_loop(i).await;
+                    } else {
+                        self_clone.working_loop(i).await;
+                    }
+                };
+                tokio::join!(work, self_clone.submission_retry_loop(i));
             });
-            thread::sleep(Duration::from_secs(3)); // Sleep for 3 seconds to avoid overwhelming the l2geth/coordinator with requests.
+            // Sleep for 3 seconds to avoid overwhelming the l2geth/coordinator with requests.
+            tokio::select! {
+                _ = sleep(Duration::from_secs(3)) => {},
+                _ = &mut shutdown_signal => {
+                    signal_received = true;
+                    break;
+                }
+            }
         }
 
+        let shutdown_requested = signal_received
+            || tokio::select! {
+                _ = &mut health_check_server_task => false,
+                _ = async { while provers.join_next().await.is_some() {} } => false,
+                _ = &mut shutdown_signal => true,
+            };
+        if !shutdown_requested {
+            return;
+        }
+
+        info!(
+            drain_timeout_sec = self_arc.drain_timeout_sec,
+            "Shutdown requested, waiting for in-flight tasks to drain"
+        );
+        self_arc.shutdown.send_replace(true);
+        let drain_timeout = Duration::from_secs(self_arc.drain_timeout_sec);
+        if timeout(drain_timeout, async {
+            while provers.join_next().await.is_some() {}
+        })
+        .await
+        .is_err()
+        {
+            provers.shutdown().await;
+            if self_arc.cancel_on_shutdown {
+                warn!(
+                    "Drain timeout elapsed, aborted remaining workers and cancelling their tasks"
+                );
+                self_arc.cancel_persisted_tasks().await;
+            } else {
+                warn!("Drain timeout elapsed, aborted remaining workers; their tasks stay persisted in db");
+            }
+        }
+        let _ = health_shutdown.send(());
+        let _ = health_check_server_task.await;
+        info!("Prover shut down");
+    }
+
+    fn coordinator_client(&self, i: usize) -> Arc<CoordinatorClient> {
+        Arc::clone(&self.coordinator_clients[i].read().unwrap())
+    }
+
+    /// Returns the network served by worker `i`.
+    fn network(&self, i: usize) -> Arc<Network> {
+        Arc::clone(&self.networks[self.worker_networks[i]].read().unwrap())
+    }
+
+    fn polling(&self) -> PollingConfig {
+        self.polling.read().unwrap().clone()
+    }
+
+    fn is_shutting_down(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+
+    /// Sleeps for `duration`, returning early if a shutdown is requested meanwhile.
+    async fn sleep_unless_shutdown(&self, duration: Duration) {
+        let mut shutdown = self.shutdown.subscribe();
         tokio::select! {
-            _ = health_check_server_task => {},
-            _ = async { while provers.join_next().await.is_some() {} } => {},
+            _ = sleep(duration) => {},
+            _ = shutdown.wait_for(|shutdown| *shutdown) => {},
         }
     }
 
+    async fn block_cache_eviction_loop(&self) {
+        let interval = Duration::from_secs(self.block_cache.eviction_interval_sec);
+        while !self.is_shutting_down() {
+            let evicted = self.db.evict_block_cache(self.block_cache.max_age_sec);
+            info!(evicted, "Evicted expired entries from the block cache");
+            self.sleep_unless_shutdown(interval).await;
+        }
+    }
+
+    /// Logs in with the first worker of every network.
     async fn test_coordinator_connection(&self) {
-        self.coordinator_clients[0]
-            .get_token(true)
-            .await
-            .expect("Failed to login to coordinator");
+        for network_index in 0..self.networks.len() {
+            let Some(i) = self
+                .worker_networks
+                .iter()
+                .position(|n| *n == network_index)
+            else {
+                continue;
+            };
+            self.coordinator_client(i)
+                .get_token(true)
+                .await
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "Failed to login to coordinator of network {}: {e}",
+                        self.network(i).name
+                    )
+                });
+        }
+    }
+
+    /// Waits for a free task slot if `max_concurrent_tasks` is set. Waiting workers are served in
+    /// FIFO order, so that the networks take turns on the proving service.
+    async fn acquire_task_slot(&self) -> Option<OwnedSemaphorePermit> {
+        let task_slots = self.task_slots.as_ref()?;
+        Some(
+            Arc::clone(task_slots)
+                .acquire_owned()
+                .await
+                .expect("task slots are never closed"),
+        )
     }
 
     #[instrument(skip(self))]
     async fn working_loop(&self, i: usize) {
+        let mut no_task_backoff = Backoff::new(self.polling().no_task);
+        let mut error_backoff = Backoff::new(self.polling().error);
         loop {
-            let coordinator_client = &self.coordinator_clients[i];
+            let network = self.network(i);
+            let coordinator_client = self.coordinator_client(i);
             let prover_name = &coordinator_client.prover_name;
+            let polling = self.polling();
+            no_task_backoff.set_interval(polling.no_task);
+            error_backoff.set_interval(polling.error);
+
+            if self.is_shutting_down() {
+                info!(
+                    ?prover_name,
+                    "Shutdown requested, worker stops taking new tasks"
+                );
+                break;
+            }
+
+            info!(?prover_name, network = ?network.name, "Getting task from coordinator");
+
+            let task_slot = self.acquire_task_slot().await;
+            let result = self.handle_task(&network, &coordinator_client).await;
+            drop(task_slot);
+            let delay = match result {
+                Ok(TaskOutcome::Handled) => {
+                    no_task_backoff.reset();
+                    error_backoff.reset();
+                    continue;
+                }
+                Ok(TaskOutcome::NoTask) => {
+                    info!(?prover_name, "No task available");
+                    error_backoff.reset();
+                    no_task_backoff.next_delay()
+                }
+                Err(e) => {
+                    error!(?prover_name, ?e, "Error handling task");
+                    no_task_backoff.reset();
+                    error_backoff.next_delay()
+                }
+            };
+
+            self.sleep_unless_shutdown(delay).await;
+        }
+    }
+
+    /// Pipelined variant of `working_loop`: the next `prefetch_depth` coordinator tasks are fetched
+    /// and their inputs built while the current one is proving. Prefetched tasks are kept in memory
+    /// only: on a graceful shutdown they are reported back to the coordinator as failed, but on a
+    /// crash they are left to the coordinator to reassign once they time out.
+    #[instrument(skip(self))]
+    async fn pipelined_working_loop(&self, i: usize) {
+        let prover_name = self.coordinator_client(i).prover_name.clone();
+        let prover_name = &prover_name;
+
+        let task_slot = self.acquire_task_slot().await;
+        if let Some(Err(e)) = self
+            .resume_persisted_task(&self.network(i), &self.coordinator_client(i))
+            .await
+        {
+            error!(?prover_name, ?e, "Error handling persisted task");
+        }
+        drop(task_slot);
 
-            info!(?prover_name, "Getting task from coordinator");
+        let (prefetched_tx, mut prefetched_rx) =
+            mpsc::channel::<PrefetchedTask>(self.prefetch_depth);
 
-            if let Err(e) = self.handle_task(coordinator_client).await {
-                error!(?prover_name, ?e, "Error handling task");
+        let prefetch = async move {
+            let mut no_task_backoff = Backoff::new(self.polling().no_task);
+            let mut error_backoff = Backoff::new(self.polling().error);
+            loop {
+                let polling = self.polling();
+                no_task_backoff.set_interval(polling.no_task);
+                error_backoff.set_interval(polling.error);
+                if self.is_shutting_down() {
+                    info!(
+                        ?prover_name,
+                        "Shutdown requested, worker stops prefetching tasks"
+                    );
+                    break;
+                }
+                // Reserve a slot before fetching so that at most `prefetch_depth` tasks are
+                // held by this worker without being handed to the proving service.
+                let Ok(permit) = prefetched_tx.reserve().await else {
+                    break;
+                };
+                let task_slot = self.acquire_task_slot().await;
+                // Shutdown may have started while waiting for the permit or the task slot.
+                if self.is_shutting_down() {
+                    info!(
+                        ?prover_name,
+                        "Shutdown requested, worker stops prefetching tasks"
+                    );
+                    break;
+                }
+                let network = self.network(i);
+                let coordinator_client = self.coordinator_client(i);
+
+                info!(?prover_name, network = ?network.name, "Prefetching task from coordinator");
+                let delay = match self.prefetch_task(&network, &coordinator_client).await {
+                    Ok(Some((coordinator_task, proving_input))) => {
+                        permit.send(PrefetchedTask {
+                            network,
+                            coordinator_client,
+                            coordinator_task,
+                            proving_input,
+                            task_slot,
+                        });
+                        no_task_backoff.reset();
+                        error_backoff.reset();
+                        continue;
+                    }
+                    Ok(None) => {
+                        info!(?prover_name, "No task available");
+                        error_backoff.reset();
+                        no_task_backoff.next_delay()
+                    }
+                    Err(e) => {
+                        error!(?prover_name, ?e, "Error prefetching task");
+                        no_task_backoff.reset();
+                        error_backoff.next_delay()
+                    }
+                };
+                drop(permit);
+                drop(task_slot);
+                self.sleep_unless_shutdown(delay).await;
             }
+        };
 
-            sleep(Duration::from_secs(WORKER_SLEEP_SEC)).await;
+        let prove = async {
+            while let Some(prefetched) = prefetched_rx.recv().await {
+                if self.is_shutting_down() {
+                    info!(
+                        ?prover_name,
+                        coordinator_task_uuid = ?prefetched.coordinator_task.uuid,
+                        coordinator_task_id = ?prefetched.coordinator_task.task_id,
+                        "Shutdown requested, handing prefetched task back to coordinator"
+                    );
+                    // Reported as not the circuit's fault, so that the coordinator hands the task
+                    // out again right away instead of waiting for it to time out.
+                    if let Err(e) = self
+                        .submit_proof(
+                            &prefetched.coordinator_client,
+                            &prefetched.coordinator_task,
+                            proving_service::QueryTaskResponse {
+                                failure_kind: Some(FailureKind::Transient),
+                                ..Default::default()
+                            },
+                            ProofStatus::Error,
+                            Some("prover shut down before proving the task".to_string()),
+                        )
+                        .await
+                    {
+                        error!(?prover_name, ?e, "Error handing prefetched task back");
+                    }
+                    continue;
+                }
+                if let Err(e) = self
+                    .prove_prefetched_task(
+                        &prefetched.network,
+                        &prefetched.coordinator_client,
+                        &prefetched.coordinator_task,
+                        prefetched.proving_input,
+                    )
+                    .await
+                {
+                    error!(?prover_name, ?e, "Error handling task");
+                }
+                drop(prefetched.task_slot);
+            }
+        };
+
+        tokio::join!(prefetch, prove);
+    }
+
+    async fn handle_task(
+        &self,
+        network: &Network,
+        coordinator_client: &CoordinatorClient,
+    ) -> anyhow::Result<TaskOutcome> {
+        if let Some(result) = self
+            .resume_persisted_task(network, coordinator_client)
+            .await
+        {
+            return result.map(|_| TaskOutcome::Handled);
         }
+
+        let Some(coordinator_task) = self
+            .get_coordinator_task(network, coordinator_client)
+            .await?
+        else {
+            return Ok(TaskOutcome::NoTask);
+        };
+        let (proving_input, proving_task) = self
+            .request_proving(network, coordinator_client, &coordinator_task)
+            .await?;
+        self.handle_proving_progress(
+            network,
+            coordinator_client,
+            &coordinator_task,
+            proving_task.task_id,
+            Some(proving_input),
+        )
+        .await?;
+        Ok(TaskOutcome::Handled)
     }
 
-    async fn handle_task(&self, coordinator_client: &CoordinatorClient) -> anyhow::Result<()> {
-        if let (Some(coordinator_task), Some(mut proving_task_id)) = self
+    /// Picks up the task persisted in db for this worker, if any, and drives it to completion.
+    async fn resume_persisted_task(
+        &self,
+        network: &Network,
+        coordinator_client: &CoordinatorClient,
+    ) -> Option<anyhow::Result<()>> {
+        let (Some(coordinator_task), Some(mut proving_task_id)) = self
             .db
             .get_task(coordinator_client.key_signer.get_public_key())
+        else {
+            return None;
+        };
+
+        let task_id = coordinator_task.clone().task_id;
+        log::debug!("got previous task from db, task_id: {task_id}");
+        // Tasks persisted before the task history existed are timed from now on.
+        if self.db.get_task_record(&coordinator_task.uuid).is_none() {
+            self.db.record_task_fetched(
+                coordinator_client.key_signer.get_public_key(),
+                &coordinator_task,
+            );
+            self.db
+                .record_task_started(&coordinator_task.uuid, proving_task_id.clone());
+        }
+        // Queue time is checked on the first status query, once the task is known to be queued.
+        if let Some(failure_msg) =
+            self.expired_deadline(&coordinator_task, TaskStatus::Proving, unix_now())
         {
-            let task_id = coordinator_task.clone().task_id;
-            log::debug!("got previous task from db, task_id: {task_id}");
-            if self.proving_service.read().await.is_local() {
-                let proving_task = self
-                    .request_proving(coordinator_client, &coordinator_task)
-                    .await?;
-                proving_task_id = proving_task.task_id
-            }
-            return self
-                .handle_proving_progress(coordinator_client, &coordinator_task, proving_task_id)
+            return Some(
+                self.abandon_expired_task(
+                    coordinator_client,
+                    &coordinator_task,
+                    &proving_task_id,
+                    failure_msg,
+                )
+                .await,
+            );
+        }
+        let mut proving_input = None;
+        if self.proving_service.is_local() {
+            // The task is proved again from scratch, so the run started before the restart is
+            // dropped.
+            self.cancel_proving_task(&coordinator_client.prover_name, &proving_task_id)
                 .await;
+            match self
+                .request_proving(network, coordinator_client, &coordinator_task)
+                .await
+            {
+                Ok((input, proving_task)) => {
+                    proving_task_id = proving_task.task_id;
+                    proving_input = Some(input);
+                }
+                Err(e) => return Some(Err(e)),
+            }
         }
+        Some(
+            self.handle_proving_progress(
+                network,
+                coordinator_client,
+                &coordinator_task,
+                proving_task_id,
+                proving_input,
+            )
+            .await,
+        )
+    }
+
+    async fn prefetch_task(
+        &self,
+        network: &Network,
+        coordinator_client: &CoordinatorClient,
+    ) -> anyhow::Result<Option<(GetTaskResponseData, ProveRequest)>> {
+        let Some(coordinator_task) = self
+            .get_coordinator_task(network, coordinator_client)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let proving_input = self
+            .prepare_proving_input(network, coordinator_client, &coordinator_task)
+            .await?;
+        Ok(Some((coordinator_task, proving_input)))
+    }
 
-        let coordinator_task = self.get_coordinator_task(coordinator_client).await?;
+    async fn prove_prefetched_task(
+        &self,
+        network: &Network,
+        coordinator_client: &CoordinatorClient,
+        coordinator_task: &GetTaskResponseData,
+        proving_input: ProveRequest,
+    ) -> anyhow::Result<()> {
         let proving_task = self
-            .request_proving(coordinator_client, &coordinator_task)
+            .prove_input(coordinator_client, coordinator_task, proving_input.clone())
             .await?;
-        self.handle_proving_progress(coordinator_client, &coordinator_task, proving_task.task_id)
-            .await
+        self.handle_proving_progress(
+            network,
+            coordinator_client,
+            coordinator_task,
+            proving_task.task_id,
+            Some(proving_input),
+        )
+        .await
     }
 
     async fn get_coordinator_task(
         &self,
+        network: &Network,
         coordinator_client: &CoordinatorClient,
-    ) -> anyhow::Result<GetTaskResponseData> {
-        let get_task_request = self.build_get_task_request().await?;
+    ) -> anyhow::Result<Option<GetTaskResponseData>> {
+        let get_task_request = self.build_get_task_request(network).await?;
         let coordinator_task = coordinator_client.get_task(&get_task_request).await?;
 
+        if coordinator_task.errcode == ErrorCode::ErrCoordinatorEmptyProofData {
+            return Ok(None);
+        }
         if coordinator_task.errcode != ErrorCode::Success {
             anyhow::bail!(
                 "Failed to get task, errcode: {:?}, errmsg: {:?}",
@@ -134,18 +593,43 @@ where
             );
         }
 
-        coordinator_task
-            .data
-            .ok_or_else(|| anyhow::anyhow!("No task available"))
+        if let Some(task) = &coordinator_task.data {
+            metrics::inc_tasks_fetched(task.task_type, &coordinator_client.prover_name);
+            self.db
+                .record_task_fetched(coordinator_client.key_signer.get_public_key(), task);
+        }
+        Ok(coordinator_task.data)
     }
 
+    /// Builds the input of a task and hands it to the proving service, returning the input too so
+    /// that it can be resubmitted without building it again.
     async fn request_proving(
         &self,
+        network: &Network,
         coordinator_client: &CoordinatorClient,
         coordinator_task: &GetTaskResponseData,
-    ) -> anyhow::Result<proving_service::ProveResponse> {
-        let proving_input = match self.build_proving_input(coordinator_task).await {
-            Ok(result) => result,
+    ) -> anyhow::Result<(ProveRequest, proving_service::ProveResponse)> {
+        let proving_input = self
+            .prepare_proving_input(network, coordinator_client, coordinator_task)
+            .await?;
+        let proving_task = self
+            .prove_input(coordinator_client, coordinator_task, proving_input.clone())
+            .await?;
+        Ok((proving_input, proving_task))
+    }
+
+    async fn prepare_proving_input(
+        &self,
+        network: &Network,
+        coordinator_client: &CoordinatorClient,
+        coordinator_task: &GetTaskResponseData,
+    ) -> anyhow::Result<ProveRequest> {
+        let started = Instant::now();
+        match self.build_proving_input(network, coordinator_task).await {
+            Ok(result) => {
+                metrics::observe_input_build(coordinator_task.task_type, started);
+                Ok(result)
+            }
             Err(error) => {
                 self.submit_proof(
                     coordinator_client,
@@ -163,19 +647,46 @@ where
                     error,
                 );
             }
-        };
+        }
+    }
 
-        let proving_task = self
-            .proving_service
-            .write()
-            .await
-            .prove(proving_input)
-            .await;
+    async fn prove_input(
+        &self,
+        coordinator_client: &CoordinatorClient,
+        coordinator_task: &GetTaskResponseData,
+        proving_input: ProveRequest,
+    ) -> anyhow::Result<proving_service::ProveResponse> {
+        let mut error_backoff = Backoff::new(self.polling().error);
+        let mut attempt = 0;
+        let proving_task = loop {
+            let proving_task = self.proving_service.prove(proving_input.clone()).await;
+            match (&proving_task.error, proving_task.failure_kind) {
+                (Some(error), Some(failure_kind))
+                    if failure_kind.is_retryable() && attempt < self.max_transient_retries =>
+                {
+                    attempt += 1;
+                    warn!(
+                        prover_name = ?coordinator_client.prover_name,
+                        task_type = ?coordinator_task.task_type,
+                        coordinator_task_uuid = ?coordinator_task.uuid,
+                        coordinator_task_id = ?coordinator_task.task_id,
+                        ?error,
+                        attempt,
+                        "Transient failure requesting proving, retrying"
+                    );
+                    self.sleep_unless_shutdown(error_backoff.next_delay()).await;
+                }
+                _ => break proving_task,
+            }
+        };
         if let Some(error) = proving_task.error {
             self.submit_proof(
                 coordinator_client,
                 coordinator_task,
-                proving_service::QueryTaskResponse::default(),
+                proving_service::QueryTaskResponse {
+                    failure_kind: proving_task.failure_kind,
+                    ..Default::default()
+                },
                 ProofStatus::Error,
                 Some(format!("failed to request proving: error {:?}", error)),
             )
@@ -189,32 +700,59 @@ where
             );
         }
 
+        self.db
+            .record_task_started(&coordinator_task.uuid, proving_task.task_id.clone());
         Ok(proving_task)
     }
 
+    /// Polls a task until it finishes and submits the result. Tasks failing with a transient error
+    /// are proved again from `proving_input`, which is built first if not known yet.
     async fn handle_proving_progress(
         &self,
+        network: &Network,
         coordinator_client: &CoordinatorClient,
         coordinator_task: &GetTaskResponseData,
-        proving_service_task_id: String,
+        mut proving_service_task_id: String,
+        mut proving_input: Option<ProveRequest>,
     ) -> anyhow::Result<()> {
         let prover_name = &coordinator_client.prover_name;
         let public_key = &coordinator_client.key_signer.get_public_key();
         let task_type = coordinator_task.task_type;
         let coordinator_task_uuid = &coordinator_task.uuid;
         let coordinator_task_id = &coordinator_task.task_id;
+        let polling = self.polling();
+        let mut in_progress_backoff = Backoff::new(polling.in_progress);
+        let mut error_backoff = Backoff::new(polling.error);
+        let mut query_error_backoff = Backoff::new(polling.error);
+        let mut transient_retries = 0;
+        let picked_up_at = unix_now();
 
         loop {
             let task = self
                 .proving_service
-                .write()
-                .await
                 .query_task(QueryTaskRequest {
                     task_id: proving_service_task_id.clone(),
                 })
                 .await;
 
+            let deadline = self.task_deadline(coordinator_task, task.status, picked_up_at);
+            if task.status != TaskStatus::Unknown {
+                query_error_backoff.reset();
+            }
             match task.status {
+                TaskStatus::Queued | TaskStatus::Proving | TaskStatus::Unknown
+                    if deadline.as_ref().is_some_and(|(at, _)| unix_now() >= *at) =>
+                {
+                    let (_, failure_msg) = deadline.unwrap_or_default();
+                    self.abandon_expired_task(
+                        coordinator_client,
+                        coordinator_task,
+                        &proving_service_task_id,
+                        failure_msg,
+                    )
+                    .await?;
+                    break;
+                }
                 TaskStatus::Queued | TaskStatus::Proving => {
                     info!(
                         ?prover_name,
@@ -230,7 +768,34 @@ where
                         coordinator_task,
                         proving_service_task_id.clone(),
                     );
-                    sleep(Duration::from_secs(WORKER_SLEEP_SEC)).await;
+                    let mut delay = in_progress_backoff.next_delay();
+                    if let Some(remaining_time_sec) = task.remaining_time_sec {
+                        let near_completion = Duration::from_secs(polling.near_completion_sec);
+                        delay = delay.min(
+                            Duration::from_secs_f64(remaining_time_sec.max(0.0))
+                                .max(near_completion),
+                        );
+                    }
+                    if let Some((at, _)) = deadline {
+                        delay = delay.min(Duration::from_secs(at.saturating_sub(unix_now())));
+                    }
+                    sleep(delay).await;
+                }
+                TaskStatus::Unknown => {
+                    warn!(
+                        ?prover_name,
+                        ?task_type,
+                        ?coordinator_task_uuid,
+                        ?coordinator_task_id,
+                        ?proving_service_task_id,
+                        error = ?task.error,
+                        "Failed to query task status, querying it again"
+                    );
+                    let mut delay = query_error_backoff.next_delay();
+                    if let Some((at, _)) = deadline {
+                        delay = delay.min(Duration::from_secs(at.saturating_sub(unix_now())));
+                    }
+                    sleep(delay).await;
                 }
                 TaskStatus::Success => {
                     info!(
@@ -241,6 +806,10 @@ where
                         ?proving_service_task_id,
                         "Task proved successfully"
                     );
+                    metrics::inc_tasks_proved(task_type, prover_name);
+                    if let Some(compute_time_sec) = task.compute_time_sec {
+                        metrics::observe_proving(task_type, compute_time_sec);
+                    }
                     self.submit_proof(
                         coordinator_client,
                         coordinator_task,
@@ -252,8 +821,46 @@ where
                     self.db.delete_task(public_key.clone());
                     break;
                 }
+                TaskStatus::Failed
+                    if task.failure_kind.is_some_and(FailureKind::is_retryable)
+                        && transient_retries < self.max_transient_retries =>
+                {
+                    transient_retries += 1;
+                    warn!(
+                        ?prover_name,
+                        ?task_type,
+                        ?coordinator_task_uuid,
+                        ?coordinator_task_id,
+                        ?proving_service_task_id,
+                        task_err = ?task.error,
+                        attempt = transient_retries,
+                        "Task failed with a transient error, proving it again"
+                    );
+                    // The failed run may still hold resources on the proving service.
+                    self.cancel_proving_task(prover_name, &proving_service_task_id)
+                        .await;
+                    self.sleep_unless_shutdown(error_backoff.next_delay()).await;
+                    let proving_task = match &proving_input {
+                        // Resubmitted as is rather than built again from l2geth.
+                        Some(input) => {
+                            self.prove_input(coordinator_client, coordinator_task, input.clone())
+                                .await?
+                        }
+                        None => {
+                            let (input, proving_task) = self
+                                .request_proving(network, coordinator_client, coordinator_task)
+                                .await?;
+                            proving_input = Some(input);
+                            proving_task
+                        }
+                    };
+                    proving_service_task_id = proving_task.task_id;
+                    in_progress_backoff.reset();
+                }
                 TaskStatus::Failed => {
-                    let task_err = task.error.clone().unwrap();
+                    let task_err = task.error.clone().unwrap_or_else(|| {
+                        "proving service reported failure without an error".into()
+                    });
                     error!(
                         ?prover_name,
                         ?task_type,
@@ -280,6 +887,143 @@ where
         Ok(())
     }
 
+    /// Returns the earliest deadline of a task in `status` as a Unix timestamp, along with the
+    /// failure reported once it passes. Without a history record, for instance if writing it
+    /// failed, the task is timed from `picked_up_at`.
+    fn task_deadline(
+        &self,
+        coordinator_task: &GetTaskResponseData,
+        status: TaskStatus,
+        picked_up_at: u64,
+    ) -> Option<(u64, String)> {
+        let deadline = self
+            .task_deadlines
+            .for_proof_type(coordinator_task.task_type);
+        let (fetched_at, started_at) = match self.db.get_task_record(&coordinator_task.uuid) {
+            Some(record) => (
+                record.fetched_at,
+                record.started_at.unwrap_or(record.fetched_at),
+            ),
+            None => (picked_up_at, picked_up_at),
+        };
+        let total = deadline.max_total_sec.map(|sec| {
+            (
+                fetched_at.saturating_add(sec),
+                format!("task was not proved within {sec}s"),
+            )
+        });
+        let queued = match (status, deadline.max_queued_sec) {
+            (TaskStatus::Queued, Some(sec)) => Some((
+                started_at.saturating_add(sec),
+                format!("task stayed queued on the proving service for more than {sec}s"),
+            )),
+            _ => None,
+        };
+        total.into_iter().chain(queued).min_by_key(|(at, _)| *at)
+    }
+
+    /// Returns the failure to report if a task in `status` is past its deadline.
+    fn expired_deadline(
+        &self,
+        coordinator_task: &GetTaskResponseData,
+        status: TaskStatus,
+        picked_up_at: u64,
+    ) -> Option<String> {
+        self.task_deadline(coordinator_task, status, picked_up_at)
+            .filter(|(at, _)| unix_now() >= *at)
+            .map(|(_, failure_msg)| failure_msg)
+    }
+
+    /// Cancels a task past its deadline, reports it to the coordinator as failed and drops it.
+    async fn abandon_expired_task(
+        &self,
+        coordinator_client: &CoordinatorClient,
+        coordinator_task: &GetTaskResponseData,
+        proving_service_task_id: &str,
+        failure_msg: String,
+    ) -> anyhow::Result<()> {
+        warn!(
+            prover_name = ?coordinator_client.prover_name,
+            task_type = ?coordinator_task.task_type,
+            coordinator_task_uuid = ?coordinator_task.uuid,
+            coordinator_task_id = ?coordinator_task.task_id,
+            ?proving_service_task_id,
+            ?failure_msg,
+            "Task exceeded its deadline, cancelling it"
+        );
+        self.cancel_proving_task(&coordinator_client.prover_name, proving_service_task_id)
+            .await;
+        // Reported as not the circuit's fault, so that the coordinator hands the task out again.
+        self.submit_proof(
+            coordinator_client,
+            coordinator_task,
+            proving_service::QueryTaskResponse {
+                task_id: proving_service_task_id.to_string(),
+                failure_kind: Some(FailureKind::Transient),
+                ..Default::default()
+            },
+            ProofStatus::Error,
+            Some(failure_msg),
+        )
+        .await?;
+        self.db
+            .delete_task(coordinator_client.key_signer.get_public_key());
+        Ok(())
+    }
+
+    /// Asks the proving service to stop a task the prover no longer waits for, returning whether
+    /// it did.
+    async fn cancel_proving_task(&self, prover_name: &str, proving_service_task_id: &str) -> bool {
+        let req = CancelTaskRequest {
+            task_id: proving_service_task_id.to_string(),
+        };
+        match self.proving_service.cancel_task(req).await {
+            Ok(()) => {
+                info!(
+                    ?prover_name,
+                    ?proving_service_task_id,
+                    "Cancelled task on proving service"
+                );
+                true
+            }
+            Err(CancelTaskError::Unsupported) => false,
+            Err(e) => {
+                warn!(
+                    ?prover_name,
+                    ?proving_service_task_id,
+                    ?e,
+                    "Failed to cancel task on proving service"
+                );
+                false
+            }
+        }
+    }
+
+    /// Cancels the tasks persisted by workers aborted on shutdown. Cancelled tasks are dropped
+    /// rather than resumed on restart.
+    async fn cancel_persisted_tasks(&self) {
+        for i in 0..self.n_workers {
+            let coordinator_client = self.coordinator_client(i);
+            let public_key = coordinator_client.key_signer.get_public_key();
+            let (Some(coordinator_task), Some(proving_task_id)) =
+                self.db.get_task(public_key.clone())
+            else {
+                continue;
+            };
+            if self
+                .cancel_proving_task(&coordinator_client.prover_name, &proving_task_id)
+                .await
+            {
+                self.db.record_task_finished(
+                    &coordinator_task.uuid,
+                    TaskRecordOutcome::Failed,
+                    Some("cancelled on shutdown".to_string()),
+                );
+                self.db.delete_task(public_key);
+            }
+        }
+    }
+
     async fn submit_proof(
         &self,
         coordinator_client: &CoordinatorClient,
@@ -294,52 +1038,162 @@ where
             task_type: coordinator_task.task_type,
             status,
             proof: task.proof.unwrap_or_default(),
-            failure_type: failure_msg.as_ref().map(|_| ProofFailureType::Panic), // TODO: handle ProofFailureType::NoPanic
+            failure_type: failure_msg.as_ref().map(|_| {
+                task.failure_kind
+                    .map_or(ProofFailureType::Panic, FailureKind::proof_failure_type)
+            }),
             failure_msg,
         };
+        if status == ProofStatus::Error {
+            metrics::inc_tasks_failed(coordinator_task.task_type, &coordinator_client.prover_name);
+        }
+        let outcome = match status {
+            ProofStatus::Ok => TaskRecordOutcome::Proved,
+            ProofStatus::Error => TaskRecordOutcome::Failed,
+        };
+        self.db.record_task_finished(
+            &coordinator_task.uuid,
+            outcome,
+            submit_proof_req.failure_msg.clone(),
+        );
+
+        if let SubmissionOutcome::Retryable = self
+            .try_submit_proof(coordinator_client, &submit_proof_req, Some(&task.task_id))
+            .await
+        {
+            warn!(
+                prover_name = ?coordinator_client.prover_name,
+                ?coordinator_task.uuid,
+                ?coordinator_task.task_id,
+                "Storing proof submission in db to retry later"
+            );
+            self.db.add_pending_submission(
+                coordinator_client.key_signer.get_public_key(),
+                &submit_proof_req,
+            );
+        }
+        Ok(())
+    }
+
+    async fn try_submit_proof(
+        &self,
+        coordinator_client: &CoordinatorClient,
+        submit_proof_req: &SubmitProofRequest,
+        proving_service_task_id: Option<&str>,
+    ) -> SubmissionOutcome {
+        let prover_name = &coordinator_client.prover_name;
+        let task_type = submit_proof_req.task_type;
+        let coordinator_task_uuid = &submit_proof_req.uuid;
+        let coordinator_task_id = &submit_proof_req.task_id;
 
-        let submit_proof_result = match coordinator_client.submit_proof(&submit_proof_req).await {
+        let submit_proof_result = match coordinator_client.submit_proof(submit_proof_req).await {
             Ok(result) => result,
             Err(e) => {
-                info!(
-                    prover_name = ?coordinator_client.prover_name,
-                    ?coordinator_task.task_type,
-                    ?coordinator_task.uuid,
-                    ?coordinator_task.task_id,
-                    ?task.task_id,
+                warn!(
+                    ?prover_name,
+                    ?task_type,
+                    ?coordinator_task_uuid,
+                    ?coordinator_task_id,
+                    ?proving_service_task_id,
                     error = ?e,
                     "Failed to submit proof due to a http error"
                 );
-                return Ok(());
+                return SubmissionOutcome::Retryable;
             }
         };
 
-        if submit_proof_result.errcode != ErrorCode::Success {
-            info!(
-                prover_name = ?coordinator_client.prover_name,
-                ?coordinator_task.task_type,
-                ?coordinator_task.uuid,
-                ?coordinator_task.task_id,
-                ?task.task_id,
-                errcode = ?submit_proof_result.errcode,
-                errmsg = ?submit_proof_result.errmsg,
-                "Failed to submit proof due to coordinator error"
-            );
-        } else {
+        self.db
+            .record_task_submitted(coordinator_task_uuid, submit_proof_result.errcode);
+        match submit_proof_result.errcode {
+            ErrorCode::Success => {
+                info!(
+                    ?prover_name,
+                    ?task_type,
+                    ?coordinator_task_uuid,
+                    ?coordinator_task_id,
+                    ?proving_service_task_id,
+                    "Proof submitted successfully"
+                );
+                metrics::inc_tasks_submitted(task_type, prover_name);
+                SubmissionOutcome::Submitted
+            }
+            // Server-side and authentication failures say nothing about the proof itself.
+            ErrorCode::InternalServerError
+            | ErrorCode::ErrJWTCommonErr
+            | ErrorCode::ErrJWTTokenExpired => {
+                warn!(
+                    ?prover_name,
+                    ?task_type,
+                    ?coordinator_task_uuid,
+                    ?coordinator_task_id,
+                    ?proving_service_task_id,
+                    errcode = ?submit_proof_result.errcode,
+                    errmsg = ?submit_proof_result.errmsg,
+                    "Failed to submit proof due to a transient coordinator error"
+                );
+                SubmissionOutcome::Retryable
+            }
+            errcode => {
+                info!(
+                    ?prover_name,
+                    ?task_type,
+                    ?coordinator_task_uuid,
+                    ?coordinator_task_id,
+                    ?proving_service_task_id,
+                    ?errcode,
+                    errmsg = ?submit_proof_result.errmsg,
+                    "Failed to submit proof due to coordinator error"
+                );
+                SubmissionOutcome::Rejected
+            }
+        }
+    }
+
+    /// Resubmits the proofs stored in db for worker `i` with backoff, until the coordinator
+    /// accepts or permanently rejects each of them.
+    #[instrument(skip(self))]
+    async fn submission_retry_loop(&self, i: usize) {
+        let public_key = self.coordinator_client(i).key_signer.get_public_key();
+        let mut retry_backoff = Backoff::new(self.polling().submission_retry);
+        loop {
+            retry_backoff.set_interval(self.polling().submission_retry);
+            self.sleep_unless_shutdown(retry_backoff.next_delay()).await;
+            if self.is_shutting_down() {
+                break;
+            }
+            let coordinator_client = self.coordinator_client(i);
+
+            let pending = self.db.get_pending_submissions(public_key.clone());
+            if pending.is_empty() {
+                retry_backoff.reset();
+                continue;
+            }
+
             info!(
                 prover_name = ?coordinator_client.prover_name,
-                ?coordinator_task.task_type,
-                ?coordinator_task.uuid,
-                ?coordinator_task.task_id,
-                ?task.task_id,
-                "Proof submitted successfully"
+                count = pending.len(),
+                "Retrying pending proof submissions"
             );
+            let mut all_done = true;
+            for submission in pending {
+                match self
+                    .try_submit_proof(&coordinator_client, &submission, None)
+                    .await
+                {
+                    SubmissionOutcome::Submitted | SubmissionOutcome::Rejected => self
+                        .db
+                        .delete_pending_submission(public_key.clone(), &submission.uuid),
+                    SubmissionOutcome::Retryable => all_done = false,
+                }
+            }
+            if all_done {
+                retry_backoff.reset();
+            }
         }
-        Ok(())
     }
 
-    async fn build_get_task_request(&self) -> anyhow::Result<GetTaskRequest> {
-        let prover_height = match &self.l2geth_client {
+    async fn build_get_task_request(&self, network: &Network) -> anyhow::Result<GetTaskRequest> {
+        let prover_height = match &network.l2geth_client {
             None => None,
             Some(l2geth_client) => match l2geth_client.block_number().await {
                 Ok(block_number) => block_number.as_number().map(|num| num.as_u64()),
@@ -350,58 +1204,58 @@ where
         };
 
         Ok(GetTaskRequest {
-            task_types: self.proof_types.clone(),
+            task_types: network.proof_types.clone(),
             prover_height,
         })
     }
 
     async fn build_proving_input(
         &self,
+        network: &Network,
         task: &GetTaskResponseData,
     ) -> anyhow::Result<ProveRequest> {
         anyhow::ensure!(
-            self.proof_types.contains(&task.task_type),
-            "unsupported task type. self: {:?}, task: {:?}, coordinator_task_uuid: {:?}, coordinator_task_id: {:?}",
-            self.proof_types,
+            network.proof_types.contains(&task.task_type),
+            "unsupported task type. network: {:?}, self: {:?}, task: {:?}, coordinator_task_uuid: {:?}, coordinator_task_id: {:?}",
+            network.name,
+            network.proof_types,
             task.task_type,
             task.uuid,
             task.task_id
         );
 
         match self.circuit_type {
-            CircuitType::Halo2 => self.build_halo2_input(task).await,
+            CircuitType::Halo2 => self.build_halo2_input(network, task).await,
             CircuitType::OpenVM => {
                 #[cfg(not(feature = "openvm"))]
                 anyhow::bail!("OpenVM is not enabled in this build");
                 #[cfg(feature = "openvm")]
-                self.build_openvm_input(task).await
+                self.build_openvm_input(network, task).await
             }
             _ => anyhow::bail!("unsupported circuit type: {:?}", self.circuit_type),
         }
     }
 
-    async fn build_halo2_input(&self, task: &GetTaskResponseData) -> anyhow::Result<ProveRequest> {
+    async fn build_halo2_input(
+        &self,
+        network: &Network,
+        task: &GetTaskResponseData,
+    ) -> anyhow::Result<ProveRequest> {
         match task.task_type {
             ProofType::Undefined => {
                 unreachable!();
             }
             ProofType::Chunk => {
                 let chunk_task_detail: ChunkTaskDetail = serde_json::from_str(&task.task_data)?;
-                let serialized_traces = match self
-                    .l2geth_client
-                    .as_ref()
-                    .unwrap()
-                    .get_traces_by_hashes(&chunk_task_detail.block_hashes)
+                let input = match self
+                    .write_block_traces(network, &chunk_task_detail.block_hashes)
                     .await
                 {
-                    Ok(traces) => traces,
+                    Ok(input) => input,
                     Err(e) => {
                         anyhow::bail!("Failed to get traces by hashes: {:?}", e);
                     }
                 };
-                // Note: Manually join pre-serialized traces since they are already in JSON format.
-                // Using serde_json::to_string would escape the JSON strings, creating invalid nested JSON.
-                let input = format!("[{}]", serialized_traces.join(","));
 
                 Ok(ProveRequest {
                     proof_type: task.task_type,
@@ -414,19 +1268,115 @@ where
                 proof_type: task.task_type,
                 circuit_version: self.circuit_version.clone(),
                 hard_fork_name: task.hard_fork_name.clone(),
-                input: task.task_data.clone(),
+                input: {
+                    let task_data = task.task_data.clone();
+                    self.write_input(move |writer| writer.write_all(task_data.as_bytes()))
+                        .await?
+                },
+            }),
+        }
+    }
+
+    #[cfg(feature = "openvm")]
+    async fn build_openvm_input(
+        &self,
+        network: &Network,
+        task: &GetTaskResponseData,
+    ) -> anyhow::Result<ProveRequest> {
+        match task.task_type {
+            ProofType::Chunk => {
+                let chunk_task_detail: ChunkTaskDetail = serde_json::from_str(&task.task_data)?;
+                let trace_fetch_concurrency = network
+                    .l2geth_client
+                    .as_ref()
+                    .map_or(1, |client| client.trace_fetch_concurrency());
+                let mut block_witnesses = stream::iter(chunk_task_detail.block_hashes)
+                    .map(|block_hash| async move {
+                        self.get_block_witness(network, block_hash)
+                            .await
+                            .map_err(|e| {
+                                anyhow::anyhow!(
+                                    "Failed to build block witness for hash {:?}: {:?}",
+                                    block_hash,
+                                    e
+                                )
+                            })
+                    })
+                    .buffered(trace_fetch_concurrency);
+
+                // Witnesses are serialized straight into the input as they arrive, so at most
+                // `trace_fetch_concurrency` of them are held in memory at once. The coordinator
+                // sends block hashes in block order, so rather than sorting the witnesses, which
+                // would need all of them at once, a chunk out of order is rejected.
+                let mut writer = ProveInputWriter::new(&self.input_config)?
+                    .write_blocking(|writer| writer.write_all(b"{\"block_witnesses\":["))
+                    .await?;
+                let mut separator: &'static [u8] = b"";
+                let mut prev_number = None;
+                while let Some(witness) = block_witnesses.try_next().await? {
+                    let number = witness.header.number;
+                    if prev_number.is_some_and(|prev| prev >= number) {
+                        anyhow::bail!("block hashes of chunk task are not in block order");
+                    }
+                    prev_number = Some(number);
+                    writer = writer
+                        .write_blocking(move |writer| {
+                            writer.write_all(separator)?;
+                            serde_json::to_writer(&mut *writer, &witness)?;
+                            Ok(())
+                        })
+                        .await?;
+                    separator = b",";
+                }
+
+                let input_map = serde_json::json!({
+                    "prev_msg_queue_hash": chunk_task_detail.prev_msg_queue_hash,
+                    "fork_name": chunk_task_detail.fork_name,
+                });
+                writer = writer
+                    .write_blocking(move |writer| {
+                        writer.write_all(b"]")?;
+                        for (key, value) in input_map.as_object().into_iter().flatten() {
+                            write!(writer, ",{}:", serde_json::to_string(key)?)?;
+                            serde_json::to_writer(&mut *writer, value)?;
+                        }
+                        writer.write_all(b"}")
+                    })
+                    .await?;
+                let input = writer.finish_blocking().await?;
+
+                Ok(ProveRequest {
+                    proof_type: task.task_type,
+                    circuit_version: self.circuit_version.clone(),
+                    hard_fork_name: task.hard_fork_name.clone(),
+                    input,
+                })
+            }
+            ProofType::Batch | ProofType::Bundle => Ok(ProveRequest {
+                proof_type: task.task_type,
+                circuit_version: self.circuit_version.clone(),
+                hard_fork_name: task.hard_fork_name.clone(),
+                input: {
+                    let task_data = task.task_data.clone();
+                    self.write_input(move |writer| writer.write_all(task_data.as_bytes()))
+                        .await?
+                },
             }),
         }
     }
 
     #[cfg(feature = "openvm")]
-    async fn build_openvm_input(&self, task: &GetTaskResponseData) -> anyhow::Result<ProveRequest> {
+    async fn build_openvm_input(
+        &self,
+        network: &Network,
+        task: &GetTaskResponseData,
+    ) -> anyhow::Result<ProveRequest> {
         match task.task_type {
             ProofType::Chunk => {
                 let chunk_task_detail: ChunkTaskDetail = serde_json::from_str(&task.task_data)?;
                 let mut block_witnesses = vec![];
                 for block_hash in chunk_task_detail.block_hashes {
-                    match self.build_block_witness(block_hash).await {
+                    match self.get_block_witness(network, block_hash).await {
                         Ok(witness) => block_witnesses.push(witness),
                         Err(e) => {
                             anyhow::bail!(
@@ -439,84 +1389,276 @@ where
                 }
                 block_witnesses.sort_by(|a, b| a.header.number.cmp(&b.header.number));
 
+                // Serialized straight into the input rather than through a `serde_json::Value`,
+                // which would copy every witness.
                 let input_map = serde_json::json!({
-                    "block_witnesses": block_witnesses,
                     "prev_msg_queue_hash": chunk_task_detail.prev_msg_queue_hash,
                     "fork_name": chunk_task_detail.fork_name,
                 });
+                let input = self
+                    .write_input(move |writer| {
+                        writer.write_all(b"{\"block_witnesses\":")?;
+                        serde_json::to_writer(&mut *writer, &block_witnesses)?;
+                        for (key, value) in input_map.as_object().into_iter().flatten() {
+                            write!(writer, ",{}:", serde_json::to_string(key)?)?;
+                            serde_json::to_writer(&mut *writer, value)?;
+                        }
+                        writer.write_all(b"}")?;
+                        Ok(())
+                    })
+                    .await?;
 
                 Ok(ProveRequest {
                     proof_type: task.task_type,
                     circuit_version: self.circuit_version.clone(),
                     hard_fork_name: task.hard_fork_name.clone(),
-                    input: serde_json::to_string(&input_map)?,
+                    input,
                 })
             }
             ProofType::Batch | ProofType::Bundle => Ok(ProveRequest {
                 proof_type: task.task_type,
                 circuit_version: self.circuit_version.clone(),
                 hard_fork_name: task.hard_fork_name.clone(),
-                input: task.task_data.clone(),
+                input: {
+                    let task_data = task.task_data.clone();
+                    self.write_input(move |writer| writer.write_all(task_data.as_bytes()))
+                        .await?
+                },
             }),
             _ => anyhow::bail!("unsupported task type: {:?}", task.task_type),
         }
     }
 
+    /// Builds a proving input with `write` on a blocking thread, compressing and spooling it as
+    /// configured.
+    async fn write_input(
+        &self,
+        write: impl FnOnce(&mut ProveInputWriter) -> std::io::Result<()> + Send + 'static,
+    ) -> anyhow::Result<ProveInput> {
+        let writer = ProveInputWriter::new(&self.input_config)?;
+        Ok(writer
+            .write_blocking(write)
+            .await?
+            .finish_blocking()
+            .await?)
+    }
+
+    /// Looks a block up in the on-disk block cache, recording the hit or miss.
+    fn get_cached_block(&self, hash: &H256) -> Option<Vec<u8>> {
+        if !self.block_cache.enabled {
+            return None;
+        }
+        let cached =
+            self.db
+                .get_cached_block(self.circuit_type, hash, self.block_cache.max_age_sec);
+        metrics::inc_block_cache_lookup(self.circuit_type, cached.is_some());
+        cached
+    }
+
+    fn put_cached_block(&self, hash: &H256, data: &[u8]) {
+        if self.block_cache.enabled {
+            self.db.put_cached_block(self.circuit_type, hash, data);
+        }
+    }
+
+    /// Writes the traces of `block_hashes` into a proving input as a JSON array, in order,
+    /// fetching only the uncached ones from l2geth. Traces are written as they arrive, so at
+    /// most `trace_fetch_concurrency` of them are held in memory at once.
+    async fn write_block_traces(
+        &self,
+        network: &Network,
+        block_hashes: &[H256],
+    ) -> anyhow::Result<ProveInput> {
+        if block_hashes.is_empty() {
+            anyhow::bail!("block_hashes are empty")
+        }
+        let l2geth_client = network.l2geth_client.as_ref().unwrap();
+        let mut traces = stream::iter(block_hashes)
+            .map(|hash| async move {
+                let cached = self
+                    .get_cached_block(hash)
+                    .filter(|trace| std::str::from_utf8(trace).is_ok());
+                if let Some(trace) = cached {
+                    return anyhow::Ok(trace);
+                }
+                let trace = l2geth_client.get_block_trace_by_hash(hash).await?;
+                self.put_cached_block(hash, trace.as_bytes());
+                Ok(trace.into_bytes())
+            })
+            .buffered(l2geth_client.trace_fetch_concurrency());
+
+        // Note: Manually join pre-serialized traces since they are already in JSON format.
+        // Using serde_json::to_string would escape the JSON strings, creating invalid nested JSON.
+        let mut writer = ProveInputWriter::new(&self.input_config)?;
+        let mut separator: &'static [u8] = b"[";
+        while let Some(trace) = traces.try_next().await? {
+            writer = writer
+                .write_blocking(move |writer| {
+                    writer.write_all(separator)?;
+                    writer.write_all(&trace)
+                })
+                .await?;
+            separator = b",";
+        }
+        writer = writer
+            .write_blocking(|writer| writer.write_all(b"]"))
+            .await?;
+        Ok(writer.finish_blocking().await?)
+    }
+
+    #[cfg(feature = "openvm")]
+    async fn get_block_witness(
+        &self,
+        network: &Network,
+        hash: H256,
+    ) -> anyhow::Result<sbv_primitives::types::BlockWitness> {
+        if let Some(witness) = self
+            .get_cached_block(&hash)
+            .and_then(|witness| serde_json::from_slice(&witness).ok())
+        {
+            return Ok(witness);
+        }
+        let witness = self.build_block_witness(network, hash).await?;
+        match serde_json::to_vec(&witness) {
+            Ok(bytes) => self.put_cached_block(&hash, &bytes),
+            Err(e) => warn!(?hash, ?e, "Failed to serialize block witness for the cache"),
+        }
+        Ok(witness)
+    }
+
     #[cfg(feature = "openvm")]
     async fn build_block_witness(
         &self,
+        network: &Network,
         hash: H256,
     ) -> anyhow::Result<sbv_primitives::types::BlockWitness> {
         use sbv_utils::rpc::ProviderExt;
 
-        let client = self.l2geth_client.as_ref().unwrap();
-        let block = client
-            .provider
-            .get_block(hash)
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Block not found"))?;
-        let block_num = block.number.expect("block hash without number").as_u64();
+        // Both requests go to the same endpoint, so the block number matches the hash.
+        let client = network.l2geth_client.as_ref().unwrap();
+        client
+            .with_trace_failover("scroll_dumpBlockWitness", |provider| async move {
+                let started = Instant::now();
+                let block = provider.get_block(hash).await.map_err(anyhow::Error::from);
+                metrics::observe_l2geth_request("eth_getBlockByHash", started, &block);
+                let block = block?.ok_or_else(|| anyhow::anyhow!("Block not found"))?;
+                let block_num = block.number.expect("block hash without number").as_u64();
 
-        let provider =
-            alloy::providers::ProviderBuilder::<_, _, sbv_primitives::types::Network>::default()
-                .on_http(client.provider.provider().url().clone());
+                let alloy_provider = alloy::providers::ProviderBuilder::<
+                    _,
+                    _,
+                    sbv_primitives::types::Network,
+                >::default()
+                .on_http(provider.provider().url().clone());
 
-        let witness = provider.dump_block_witness(block_num.into()).await?;
-        witness.ok_or_else(|| anyhow::anyhow!("Failed to dump block witness"))
+                let started = Instant::now();
+                let witness = alloy_provider
+                    .dump_block_witness(block_num.into())
+                    .await
+                    .map_err(anyhow::Error::from);
+                metrics::observe_l2geth_request("scroll_dumpBlockWitness", started, &witness);
+                witness?.ok_or_else(|| anyhow::anyhow!("Failed to dump block witness"))
+            })
+            .await
+    }
+}
+
+/// A task fetched ahead by `pipelined_working_loop`, proved with the clients it was fetched with.
+struct PrefetchedTask {
+    network: Arc<Network>,
+    coordinator_client: Arc<CoordinatorClient>,
+    coordinator_task: GetTaskResponseData,
+    proving_input: ProveRequest,
+    /// Held until the task is proved.
+    task_slot: Option<OwnedSemaphorePermit>,
+}
+
+enum TaskOutcome {
+    NoTask,
+    Handled,
+}
+
+enum SubmissionOutcome {
+    Submitted,
+    /// The coordinator refused the proof, resubmitting it would not help.
+    Rejected,
+    Retryable,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Installs the SIGINT and SIGTERM handlers right away, returning a future that resolves once
+/// either signal is received.
+#[cfg(unix)]
+fn shutdown_signal() -> impl std::future::Future<Output = ()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut interrupt = signal(SignalKind::interrupt()).expect("Failed to install SIGINT handler");
+    let mut terminate = signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+    async move {
+        tokio::select! {
+            _ = interrupt.recv() => {},
+            _ = terminate.recv() => {},
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn shutdown_signal() -> impl std::future::Future<Output = ()> {
+    async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install SIGINT handler");
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::config::Config;
+    use crate::config::{Config, PollInterval, PollingConfig};
+    use crate::coordinator_handler::{GetTaskResponseData, ProofFailureType, ProofStatus, Signer};
     use crate::prover::{
         proving_service::{
-            GetVkRequest, GetVkResponse, ProveRequest, ProveResponse, QueryTaskRequest,
-            QueryTaskResponse,
+            FailureKind, GetVkRequest, GetVkResponse, ProveRequest, ProveResponse,
+            QueryTaskRequest, QueryTaskResponse,
         },
-        ProverBuilder, ProvingService,
+        CircuitType, HttpProvingService, ProofType, ProverBuilder, ProvingService, TaskStatus,
     };
+    use crate::testing::{MockCoordinator, MockProvingService};
     use async_trait::async_trait;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::{Duration, Instant};
+    use tempfile::TempDir;
     use tokio;
+    use tokio::sync::Notify;
 
-    struct MockProver {}
+    struct MockProver {
+        max_concurrency: usize,
+    }
 
     #[async_trait]
     impl ProvingService for MockProver {
         fn is_local(&self) -> bool {
             true
         }
+        fn max_concurrency(&self) -> usize {
+            self.max_concurrency
+        }
         async fn get_vks(&self, _: GetVkRequest) -> GetVkResponse {
             GetVkResponse {
                 ..Default::default()
             }
         }
-        async fn prove(&mut self, _: ProveRequest) -> ProveResponse {
+        async fn prove(&self, _: ProveRequest) -> ProveResponse {
             ProveResponse {
                 ..Default::default()
             }
         }
-        async fn query_task(&mut self, _: QueryTaskRequest) -> QueryTaskResponse {
+        async fn query_task(&self, _: QueryTaskRequest) -> QueryTaskResponse {
             QueryTaskResponse {
                 ..Default::default()
             }
@@ -525,14 +1667,387 @@ mod tests {
 
     #[tokio::test]
     async fn test_build_get_task_request() {
-        let cfg = Config::from_file("conf/config.json".to_string()).unwrap();
-        let prover_service = MockProver {};
+        let mut cfg = Config::from_file("conf/config.json".to_string()).unwrap();
+        if !cfg!(feature = "openvm") {
+            cfg.prover.circuit_type = CircuitType::Halo2;
+        }
+        let prover_service = MockProver { max_concurrency: 1 };
         let prover = ProverBuilder::new(cfg, prover_service)
             .build()
             .await
             .unwrap();
 
-        let get_task_request = prover.build_get_task_request().await;
+        let get_task_request = prover.build_get_task_request(&prover.network(0)).await;
         assert!(get_task_request.is_err())
     }
+
+    /// The sample config with its keys and db kept in `dir`, adjusted by `configure`.
+    fn test_config(dir: &TempDir, configure: impl FnOnce(&mut Config)) -> Config {
+        let dir = dir.path();
+        let mut cfg = Config::from_file("conf/config.json".to_string()).unwrap();
+        if !cfg!(feature = "openvm") {
+            cfg.prover.circuit_type = CircuitType::Halo2;
+        }
+        cfg.keys_dir = dir.join("keys").to_string_lossy().into_owned();
+        cfg.db_path = Some(dir.join("db").to_string_lossy().into_owned());
+        configure(&mut cfg);
+        cfg
+    }
+
+    #[tokio::test]
+    async fn test_local_workers_bounded_by_max_concurrency() {
+        let dir = TempDir::new().unwrap();
+        let cfg = test_config(&dir, |cfg| cfg.prover.n_workers = 2);
+
+        let result = ProverBuilder::new(cfg.clone(), MockProver { max_concurrency: 1 })
+            .build()
+            .await;
+        assert!(result.is_err());
+
+        let prover = ProverBuilder::new(cfg.clone(), MockProver { max_concurrency: 2 })
+            .build()
+            .await
+            .unwrap();
+        let prover_names: Vec<String> = (0..prover.n_workers)
+            .map(|i| prover.coordinator_client(i).prover_name.clone())
+            .collect();
+        assert_eq!(
+            prover_names,
+            vec![
+                format!("{}_0", cfg.prover_name_prefix),
+                format!("{}_1", cfg.prover_name_prefix)
+            ]
+        );
+        assert_ne!(
+            prover.coordinator_client(0).key_signer.get_public_key(),
+            prover.coordinator_client(1).key_signer.get_public_key()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_task_deadline() {
+        let dir = TempDir::new().unwrap();
+        let cfg = test_config(&dir, |cfg| {
+            cfg.prover.deadlines.batch.max_queued_sec = Some(60);
+            cfg.prover.deadlines.batch.max_total_sec = Some(3600);
+        });
+        let prover = ProverBuilder::new(cfg, MockProver { max_concurrency: 1 })
+            .build()
+            .await
+            .unwrap();
+
+        let task = GetTaskResponseData {
+            uuid: "uuid".to_string(),
+            task_id: "task".to_string(),
+            task_type: ProofType::Batch,
+            task_data: String::new(),
+            hard_fork_name: String::new(),
+        };
+        // Without a history record, the task is timed from when it was picked up.
+        let (queued_at, _) = prover
+            .task_deadline(&task, TaskStatus::Queued, 1000)
+            .unwrap();
+        assert_eq!(queued_at, 1060);
+        assert!(prover
+            .expired_deadline(&task, TaskStatus::Proving, 1000)
+            .is_some());
+
+        prover.db.record_task_fetched("key".to_string(), &task);
+        prover
+            .db
+            .record_task_started(&task.uuid, "proving".to_string());
+        let now = super::unix_now();
+        let (queued_at, _) = prover.task_deadline(&task, TaskStatus::Queued, 0).unwrap();
+        let (total_at, _) = prover.task_deadline(&task, TaskStatus::Proving, 0).unwrap();
+        assert!(queued_at >= now + 59 && queued_at < total_at);
+        assert!(prover
+            .expired_deadline(&task, TaskStatus::Queued, 0)
+            .is_none());
+
+        let chunk_task = GetTaskResponseData {
+            uuid: "chunk_uuid".to_string(),
+            task_type: ProofType::Chunk,
+            ..task
+        };
+        prover
+            .db
+            .record_task_fetched("key".to_string(), &chunk_task);
+        assert!(prover
+            .task_deadline(&chunk_task, TaskStatus::Queued, 0)
+            .is_none());
+    }
+
+    /// A config for a single worker proving batch tasks from `coordinator`, polling every second,
+    /// adjusted by `configure`.
+    fn end_to_end_config(
+        dir: &TempDir,
+        coordinator: &MockCoordinator,
+        configure: impl FnOnce(&mut Config),
+    ) -> Config {
+        let mut cfg = test_config(dir, |_| {});
+        cfg.coordinator = Some(coordinator.config());
+        cfg.l2geth = None;
+        cfg.health_listener_addr = "127.0.0.1:0".to_string();
+        cfg.prover.supported_proof_types = vec![ProofType::Batch];
+        let every_second = PollInterval {
+            interval_sec: 1,
+            max_interval_sec: None,
+            multiplier: 1.0,
+            jitter: 0.0,
+        };
+        cfg.prover.polling = PollingConfig {
+            no_task: every_second.clone(),
+            error: every_second.clone(),
+            in_progress: every_second.clone(),
+            near_completion_sec: 1,
+            submission_retry: every_second,
+        };
+        configure(&mut cfg);
+        cfg
+    }
+
+    fn batch_task(id: &str) -> GetTaskResponseData {
+        GetTaskResponseData {
+            uuid: format!("uuid-{id}"),
+            task_id: id.to_string(),
+            task_type: ProofType::Batch,
+            task_data: format!("{{\"batch\":\"{id}\"}}"),
+            hard_fork_name: "euclid".to_string(),
+        }
+    }
+
+    async fn wait_for(what: &str, mut done: impl FnMut() -> bool) {
+        let started = Instant::now();
+        while !done() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "timed out waiting for {what}"
+            );
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    /// A prover run driven by a test, which asks it to shut down and checks when it stopped.
+    #[derive(Default)]
+    struct ProverRun {
+        shutdown: Notify,
+        stopped: AtomicBool,
+    }
+
+    impl ProverRun {
+        /// Runs a prover built from `cfg` against `service` alongside `drive`, until it is shut
+        /// down and drained.
+        async fn run(
+            &self,
+            cfg: Config,
+            service: &MockProvingService,
+            drive: impl std::future::Future<Output = ()>,
+        ) {
+            let proving_service = HttpProvingService::new(service.config()).unwrap();
+            let prover = ProverBuilder::new(cfg, proving_service)
+                .build()
+                .await
+                .unwrap();
+            let run = async {
+                prover.run_until(self.shutdown.notified()).await;
+                self.stopped.store(true, Ordering::SeqCst);
+            };
+            tokio::join!(run, drive);
+        }
+
+        fn shutdown(&self) {
+            self.shutdown.notify_one();
+        }
+
+        fn is_stopped(&self) -> bool {
+            self.stopped.load(Ordering::SeqCst)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prefetched_tasks_are_handed_back_and_in_flight_task_drained_on_shutdown() {
+        let coordinator = MockCoordinator::start().await.unwrap();
+        let service = MockProvingService::start("secret").await.unwrap();
+        let dir = TempDir::new().unwrap();
+        let cfg = end_to_end_config(&dir, &coordinator, |cfg| cfg.prover.prefetch_depth = 1);
+        for id in ["task-a", "task-b", "task-c"] {
+            coordinator.push_task(batch_task(id));
+        }
+
+        let run = ProverRun::default();
+        run.run(cfg, &service, async {
+            // One task is proving, one is prefetched behind it and one is left to the
+            // coordinator.
+            wait_for("the first task to be proving", || {
+                service.prove_requests().len() == 1
+            })
+            .await;
+            wait_for("the second task to be prefetched", || {
+                coordinator.queued_tasks() == 1
+            })
+            .await;
+            run.shutdown();
+
+            // The prover keeps polling the task in flight rather than stopping.
+            let status_queries = service.status_queries();
+            wait_for("the task in flight to be polled after shutdown", || {
+                service.status_queries() >= status_queries + 2
+            })
+            .await;
+            assert!(!run.is_stopped());
+            service.finish_task("task-0", "proof");
+            wait_for("the prover to stop", || run.is_stopped()).await;
+        })
+        .await;
+
+        let submitted = coordinator.submitted_proofs();
+        assert_eq!(submitted.len(), 2);
+        assert_eq!(submitted[0].request.task_id, "task-a");
+        assert_eq!(submitted[0].request.status, ProofStatus::Ok);
+        assert_eq!(submitted[0].request.proof, "proof");
+        // The prefetched task is handed back rather than left assigned to this prover.
+        assert_eq!(submitted[1].request.task_id, "task-b");
+        assert_eq!(submitted[1].request.status, ProofStatus::Error);
+        assert_eq!(
+            submitted[1].request.failure_type,
+            Some(ProofFailureType::NoPanic)
+        );
+        assert_eq!(service.prove_requests().len(), 1);
+        assert_eq!(coordinator.queued_tasks(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_transient_failure_cancels_failed_run_and_proves_again() {
+        let coordinator = MockCoordinator::start().await.unwrap();
+        let service = MockProvingService::start("secret").await.unwrap();
+        let dir = TempDir::new().unwrap();
+        let cfg = end_to_end_config(&dir, &coordinator, |cfg| {
+            cfg.prover.max_transient_retries = 1;
+        });
+        coordinator.push_task(batch_task("task-a"));
+
+        let run = ProverRun::default();
+        run.run(cfg, &service, async {
+            wait_for("the task to be proving", || {
+                service.prove_requests().len() == 1
+            })
+            .await;
+            service.fail_task("task-0", "node lost", Some(FailureKind::Transient));
+            wait_for("the task to be proved again", || {
+                service.prove_requests().len() == 2
+            })
+            .await;
+            assert_eq!(service.cancelled_tasks(), vec!["task-0".to_string()]);
+            assert!(coordinator.submitted_proofs().is_empty());
+            let requests = service.prove_requests();
+            assert_eq!(requests[0].input, requests[1].input);
+
+            service.finish_task("task-1", "proof");
+            wait_for("the proof to be submitted", || {
+                !coordinator.submitted_proofs().is_empty()
+            })
+            .await;
+            run.shutdown();
+        })
+        .await;
+
+        let submitted = coordinator.submitted_proofs();
+        assert_eq!(submitted.len(), 1);
+        assert_eq!(submitted[0].request.status, ProofStatus::Ok);
+        assert_eq!(submitted[0].request.proof, "proof");
+    }
+
+    #[tokio::test]
+    async fn test_tasks_left_after_drain_timeout_are_cancelled_only_if_configured() {
+        for cancel_on_shutdown in [false, true] {
+            let coordinator = MockCoordinator::start().await.unwrap();
+            let service = MockProvingService::start("secret").await.unwrap();
+            let dir = TempDir::new().unwrap();
+            let cfg = end_to_end_config(&dir, &coordinator, |cfg| {
+                cfg.prover.drain_timeout_sec = 1;
+                cfg.prover.cancel_on_shutdown = cancel_on_shutdown;
+            });
+            coordinator.push_task(batch_task("task-a"));
+
+            let run = ProverRun::default();
+            run.run(cfg, &service, async {
+                wait_for("the task to be proving", || {
+                    service.prove_requests().len() == 1
+                })
+                .await;
+                run.shutdown();
+            })
+            .await;
+
+            let expected: Vec<String> = if cancel_on_shutdown {
+                vec!["task-0".to_string()]
+            } else {
+                vec![]
+            };
+            assert_eq!(service.cancelled_tasks(), expected);
+            assert!(coordinator.submitted_proofs().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_submission_is_retried() {
+        let coordinator = MockCoordinator::start().await.unwrap();
+        let service = MockProvingService::start("secret").await.unwrap();
+        let dir = TempDir::new().unwrap();
+        let cfg = end_to_end_config(&dir, &coordinator, |_| {});
+        service.set_instant_proof(Some("proof".to_string()));
+        coordinator.fail_submissions(2);
+        coordinator.push_task(batch_task("task-a"));
+
+        let run = ProverRun::default();
+        run.run(cfg, &service, async {
+            wait_for("the proof to be resubmitted", || {
+                !coordinator.submitted_proofs().is_empty()
+            })
+            .await;
+            run.shutdown();
+        })
+        .await;
+
+        let submitted = coordinator.submitted_proofs();
+        assert_eq!(submitted.len(), 1);
+        assert_eq!(submitted[0].request.task_id, "task-a");
+        assert_eq!(submitted[0].request.proof, "proof");
+        assert_eq!(service.prove_requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_task_queued_past_deadline_is_cancelled_and_reported() {
+        let coordinator = MockCoordinator::start().await.unwrap();
+        let service = MockProvingService::start("secret").await.unwrap();
+        let dir = TempDir::new().unwrap();
+        let cfg = end_to_end_config(&dir, &coordinator, |cfg| {
+            cfg.prover.deadlines.batch.max_queued_sec = Some(1);
+        });
+        coordinator.push_task(batch_task("task-a"));
+
+        let run = ProverRun::default();
+        run.run(cfg, &service, async {
+            wait_for("the expired task to be reported", || {
+                !coordinator.submitted_proofs().is_empty()
+            })
+            .await;
+            run.shutdown();
+        })
+        .await;
+
+        assert_eq!(service.cancelled_tasks(), vec!["task-0".to_string()]);
+        let submitted = coordinator.submitted_proofs();
+        assert_eq!(submitted.len(), 1);
+        assert_eq!(submitted[0].request.status, ProofStatus::Error);
+        assert_eq!(
+            submitted[0].request.failure_type,
+            Some(ProofFailureType::NoPanic)
+        );
+        assert!(submitted[0]
+            .request
+            .failure_msg
+            .as_deref()
+            .is_some_and(|msg| msg.contains("stayed queued")));
+    }
 }
@@ -0,0 +1,79 @@
+use crate::config::PollInterval;
+use rand::Rng;
+use std::time::Duration;
+
+/// Exponential backoff with jitter over a configured `PollInterval`.
+pub struct Backoff {
+    interval: PollInterval,
+    attempt: u32,
+}
+
+impl Backoff {
+    pub fn new(interval: PollInterval) -> Self {
+        Self {
+            interval,
+            attempt: 0,
+        }
+    }
+
+    /// Switches to a new interval, e.g. after a config reload, keeping the current attempt.
+    pub fn set_interval(&mut self, interval: PollInterval) {
+        self.interval = interval;
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    /// Returns the delay before the next poll and advances the backoff.
+    pub fn next_delay(&mut self) -> Duration {
+        let base = self.interval.interval_sec as f64;
+        let secs = match self.interval.max_interval_sec {
+            Some(max) => {
+                (base * self.interval.multiplier.powi(self.attempt as i32)).min(max as f64)
+            }
+            None => base,
+        };
+        self.attempt = self.attempt.saturating_add(1);
+
+        let jitter = self.interval.jitter.clamp(0.0, 1.0);
+        let factor = if jitter > 0.0 {
+            rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter)
+        } else {
+            1.0
+        };
+        Duration::from_secs_f64((secs * factor).max(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_until_cap() {
+        let mut backoff = Backoff::new(PollInterval {
+            interval_sec: 5,
+            max_interval_sec: Some(30),
+            multiplier: 2.0,
+            jitter: 0.0,
+        });
+        let delays: Vec<u64> = (0..5).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, vec![5, 10, 20, 30, 30]);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay().as_secs(), 5);
+    }
+
+    #[test]
+    fn test_backoff_without_cap_is_fixed() {
+        let mut backoff = Backoff::new(PollInterval {
+            interval_sec: 20,
+            max_interval_sec: None,
+            multiplier: 2.0,
+            jitter: 0.0,
+        });
+        assert_eq!(backoff.next_delay().as_secs(), 20);
+        assert_eq!(backoff.next_delay().as_secs(), 20);
+    }
+}
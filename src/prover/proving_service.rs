@@ -1,12 +1,108 @@
-use super::ProofType;
+use super::{ProofType, ProveInput};
+use crate::coordinator_handler::ProofFailureType;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
 
+/// A proving backend shared by every worker. Methods take `&self` so that workers can prove and
+/// poll concurrently; backends keep any mutable state behind their own locks.
 #[async_trait]
 pub trait ProvingService {
     fn is_local(&self) -> bool;
+    /// Number of tasks a local proving service can prove at once, bounding the number of workers
+    /// it can be used with.
+    fn max_concurrency(&self) -> usize {
+        1
+    }
+    async fn get_vks(&self, req: GetVkRequest) -> GetVkResponse;
+    async fn prove(&self, req: ProveRequest) -> ProveResponse;
+    async fn query_task(&self, req: QueryTaskRequest) -> QueryTaskResponse;
+    /// Stops a task so that it no longer uses the backend's compute. Called for tasks the SDK
+    /// abandons: on shutdown, past its deadline, and when a persisted task is dropped.
+    async fn cancel_task(&self, _req: CancelTaskRequest) -> Result<(), CancelTaskError> {
+        Err(CancelTaskError::Unsupported)
+    }
+    /// Reports whether the proving service is able to accept work, for the readiness endpoint.
+    async fn health_check(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// The `ProvingService` API from before it took `&self`, for backends that need exclusive
+/// access to prove or query a task. Wrap one in [`ExclusiveProvingService`] to use it.
+#[async_trait]
+pub trait ProvingServiceMut {
+    fn is_local(&self) -> bool;
+    fn max_concurrency(&self) -> usize {
+        1
+    }
     async fn get_vks(&self, req: GetVkRequest) -> GetVkResponse;
     async fn prove(&mut self, req: ProveRequest) -> ProveResponse;
     async fn query_task(&mut self, req: QueryTaskRequest) -> QueryTaskResponse;
+    async fn cancel_task(&mut self, _req: CancelTaskRequest) -> Result<(), CancelTaskError> {
+        Err(CancelTaskError::Unsupported)
+    }
+    async fn health_check(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Adapts a [`ProvingServiceMut`] to [`ProvingService`] by serializing `prove` and `query_task`
+/// calls behind a lock. A backend with `max_concurrency` above 1 must then return from `prove`
+/// once the task is started rather than once it is proved.
+pub struct ExclusiveProvingService<T> {
+    inner: RwLock<T>,
+    is_local: bool,
+    max_concurrency: usize,
+}
+
+impl<T: ProvingServiceMut> ExclusiveProvingService<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            is_local: inner.is_local(),
+            max_concurrency: inner.max_concurrency(),
+            inner: RwLock::new(inner),
+        }
+    }
+}
+
+#[async_trait]
+impl<T> ProvingService for ExclusiveProvingService<T>
+where
+    T: ProvingServiceMut + Send + Sync,
+{
+    fn is_local(&self) -> bool {
+        self.is_local
+    }
+
+    fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    async fn get_vks(&self, req: GetVkRequest) -> GetVkResponse {
+        self.inner.read().await.get_vks(req).await
+    }
+
+    async fn prove(&self, req: ProveRequest) -> ProveResponse {
+        self.inner.write().await.prove(req).await
+    }
+
+    async fn query_task(&self, req: QueryTaskRequest) -> QueryTaskResponse {
+        self.inner.write().await.query_task(req).await
+    }
+
+    async fn cancel_task(&self, req: CancelTaskRequest) -> Result<(), CancelTaskError> {
+        self.inner.write().await.cancel_task(req).await
+    }
+
+    async fn health_check(&self) -> Result<(), String> {
+        // A worker may hold the lock for the duration of a `prove` call, which is a sign of the
+        // proving service being in use rather than unhealthy.
+        let Ok(inner) = self.inner.try_read() else {
+            return Ok(());
+        };
+        inner.health_check().await
+    }
 }
 
 #[derive(Default)]
@@ -26,7 +122,7 @@ pub struct ProveRequest {
     pub proof_type: ProofType,
     pub circuit_version: String,
     pub hard_fork_name: String,
-    pub input: String,
+    pub input: ProveInput,
 }
 
 #[derive(Default)]
@@ -44,6 +140,8 @@ pub struct ProveResponse {
     pub proof: Option<String>,
     pub vk: Option<String>,
     pub error: Option<String>,
+    /// Why the task failed, if it did. Unclassified failures are reported as circuit panics.
+    pub failure_kind: Option<FailureKind>,
 }
 
 #[derive(Default)]
@@ -62,17 +160,127 @@ pub struct QueryTaskResponse {
     pub started_at: Option<f64>,
     pub finished_at: Option<f64>,
     pub compute_time_sec: Option<f64>,
+    /// Estimated time until the task finishes, if the proving service can tell.
+    pub remaining_time_sec: Option<f64>,
     pub input: Option<String>,
     pub proof: Option<String>,
     pub vk: Option<String>,
     pub error: Option<String>,
+    /// Why the task failed, if it did. Unclassified failures are reported as circuit panics.
+    pub failure_kind: Option<FailureKind>,
 }
 
-#[derive(Debug, PartialEq, Default)]
+#[derive(Default)]
+pub struct CancelTaskRequest {
+    pub task_id: String,
+}
+
+#[derive(Debug)]
+pub enum CancelTaskError {
+    /// The proving service cannot cancel tasks.
+    Unsupported,
+    Failed(String),
+}
+
+impl std::fmt::Display for CancelTaskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CancelTaskError::Unsupported => write!(f, "cancelling tasks is not supported"),
+            CancelTaskError::Failed(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for CancelTaskError {}
+
+#[derive(Debug, PartialEq, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TaskStatus {
     #[default]
     Queued,
     Proving,
     Success,
     Failed,
+    /// The status could not be queried, e.g. the proving service was unreachable. Never sent by
+    /// the proving service; the task is queried again.
+    #[serde(skip)]
+    Unknown,
+}
+
+/// Classification of a proving failure, deciding how it is reported to the coordinator and
+/// whether the SDK retries the task locally first.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// The circuit panicked on this input; proving it again will fail the same way.
+    CircuitPanic,
+    /// The input could not be proved as given, e.g. it failed to deserialize.
+    InvalidInput,
+    /// The prover ran out of memory.
+    OutOfMemory,
+    /// A failure of the proving infrastructure unrelated to the task, e.g. a lost machine or a
+    /// network error.
+    Transient,
+}
+
+impl FailureKind {
+    pub fn proof_failure_type(self) -> ProofFailureType {
+        match self {
+            FailureKind::CircuitPanic | FailureKind::InvalidInput => ProofFailureType::Panic,
+            FailureKind::OutOfMemory | FailureKind::Transient => ProofFailureType::NoPanic,
+        }
+    }
+
+    /// Whether proving the task again on this prover may succeed.
+    pub fn is_retryable(self) -> bool {
+        matches!(self, FailureKind::Transient)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct CountingProver {
+        proved: usize,
+    }
+
+    #[async_trait]
+    impl ProvingServiceMut for CountingProver {
+        fn is_local(&self) -> bool {
+            true
+        }
+        async fn get_vks(&self, _: GetVkRequest) -> GetVkResponse {
+            GetVkResponse::default()
+        }
+        async fn prove(&mut self, _: ProveRequest) -> ProveResponse {
+            self.proved += 1;
+            ProveResponse {
+                task_id: self.proved.to_string(),
+                ..Default::default()
+            }
+        }
+        async fn query_task(&mut self, req: QueryTaskRequest) -> QueryTaskResponse {
+            QueryTaskResponse {
+                task_id: req.task_id,
+                ..Default::default()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exclusive_proving_service() {
+        let service = ExclusiveProvingService::new(CountingProver::default());
+        assert!(service.is_local());
+        assert_eq!(service.max_concurrency(), 1);
+        let (first, second) = tokio::join!(
+            service.prove(ProveRequest::default()),
+            service.prove(ProveRequest::default())
+        );
+        let mut task_ids = vec![first.task_id, second.task_id];
+        task_ids.sort();
+        assert_eq!(task_ids, vec!["1", "2"]);
+        assert!(service.health_check().await.is_ok());
+    }
 }
@@ -0,0 +1,293 @@
+use super::{Network, Prover, ProvingService};
+use crate::{
+    config::Config, coordinator_handler::CoordinatorClient, prover::proving_service::GetVkRequest,
+    tracing_handler::L2gethClient,
+};
+use serde_json::Value;
+use std::{
+    collections::BTreeSet,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::time::sleep;
+use tracing::{error, info};
+
+/// How often the config files are checked for changes.
+const CONFIG_POLL_INTERVAL_SEC: u64 = 10;
+
+/// Fields that can change while the prover is running, as paths into the config.
+const LIVE_FIELDS: &[&[&str]] = &[
+    &["coordinator"],
+    &["l2geth"],
+    &["prover", "supported_proof_types"],
+    &["prover", "polling"],
+];
+/// Fields of each entry of `networks` that can change while the prover is running.
+const LIVE_NETWORK_FIELDS: &[&str] = &["coordinator", "l2geth", "supported_proof_types"];
+
+/// Where the prover reloads its config from.
+pub struct ConfigSource {
+    files: Vec<PathBuf>,
+    load: Box<dyn Fn() -> anyhow::Result<Config> + Send + Sync>,
+}
+
+impl ConfigSource {
+    pub fn new<F>(files: Vec<PathBuf>, load: F) -> Self
+    where
+        F: Fn() -> anyhow::Result<Config> + Send + Sync + 'static,
+    {
+        Self {
+            files,
+            load: Box::new(load),
+        }
+    }
+
+    fn modified_times(&self) -> Vec<Option<SystemTime>> {
+        self.files
+            .iter()
+            .map(|file| std::fs::metadata(file).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+}
+
+impl<Backend> Prover<Backend>
+where
+    Backend: ProvingService + Send + Sync + 'static,
+{
+    /// Reloads the config on SIGHUP or when a config file changes, until shutdown.
+    pub(super) async fn config_reload_loop(&self) {
+        let Some(source) = &self.config_source else {
+            return;
+        };
+        #[cfg(unix)]
+        let mut sighup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+        {
+            Ok(signal) => Some(signal),
+            Err(e) => {
+                error!(?e, "Failed to install SIGHUP handler");
+                None
+            }
+        };
+        let mut shutdown = self.shutdown.subscribe();
+        let mut modified = source.modified_times();
+        loop {
+            #[cfg(unix)]
+            let hangup = async {
+                match sighup.as_mut() {
+                    Some(signal) => {
+                        signal.recv().await;
+                    }
+                    None => std::future::pending().await,
+                }
+            };
+            #[cfg(not(unix))]
+            let hangup = std::future::pending::<()>();
+
+            tokio::select! {
+                _ = shutdown.wait_for(|shutdown| *shutdown) => break,
+                _ = hangup => info!("SIGHUP received, reloading config"),
+                _ = sleep(Duration::from_secs(CONFIG_POLL_INTERVAL_SEC)) => {
+                    if source.modified_times() == modified {
+                        continue;
+                    }
+                    info!("Config file changed, reloading config");
+                }
+            }
+            modified = source.modified_times();
+            if let Err(e) = self.reload_config().await {
+                error!(?e, "Failed to reload config, keeping the current one");
+            }
+        }
+    }
+
+    /// Loads the config again and applies it if only the fields in `LIVE_FIELDS` and
+    /// `LIVE_NETWORK_FIELDS` changed. Workers switch to the new coordinator and l2geth clients
+    /// between tasks; tasks in flight finish with the clients they started with.
+    pub async fn reload_config(&self) -> anyhow::Result<()> {
+        let source = self
+            .config_source
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("prover was built without a config source"))?;
+        let new_cfg = (source.load)()?;
+        new_cfg.validate()?;
+        let old_cfg = self.config.lock().unwrap().clone();
+
+        let changes = non_reloadable_changes(&old_cfg, &new_cfg)?;
+        if !changes.is_empty() {
+            anyhow::bail!(
+                "changing {} requires a restart, nothing was reloaded",
+                changes.join(", ")
+            );
+        }
+
+        // Everything is built before anything is swapped in, so a failure leaves the prover as
+        // it was.
+        let mut networks = vec![];
+        let mut coordinator_clients = vec![];
+        for (network_index, (old_network, new_network)) in old_cfg
+            .networks()?
+            .into_iter()
+            .zip(new_cfg.networks()?)
+            .enumerate()
+        {
+            if serde_json::to_value(&old_network)? == serde_json::to_value(&new_network)? {
+                continue;
+            }
+            let name = new_network.name;
+            let proof_types = new_network.supported_proof_types.unwrap_or_default();
+
+            // Coordinator clients are only replaced if what they send changed, so that the others
+            // keep their login and the readiness it reports.
+            let coordinator_changed = serde_json::to_value(&old_network.coordinator)?
+                != serde_json::to_value(&new_network.coordinator)?
+                || old_network.supported_proof_types.unwrap_or_default() != proof_types;
+            if coordinator_changed {
+                let get_vk_request = GetVkRequest {
+                    proof_types: proof_types.clone(),
+                    circuit_version: self.circuit_version.clone(),
+                };
+                let get_vk_response = self.proving_service.get_vks(get_vk_request).await;
+                if let Some(error) = get_vk_response.error {
+                    anyhow::bail!("network {name}: failed to get vk: {}", error);
+                }
+
+                for i in (0..self.n_workers).filter(|i| self.worker_networks[*i] == network_index) {
+                    let current = self.coordinator_client(i);
+                    let coordinator_client = CoordinatorClient::new(
+                        new_network.coordinator.clone(),
+                        new_cfg.prover_types_for(&proof_types),
+                        get_vk_response.vks.clone(),
+                        current.prover_name.clone(),
+                        current.prover_provider_type,
+                        Arc::clone(&current.key_signer),
+                    )?;
+                    coordinator_clients.push((i, Arc::new(coordinator_client)));
+                }
+            }
+
+            // Likewise the l2geth client is kept if its config did not change, so that endpoints
+            // it demoted stay in their cooldown.
+            let l2geth_client = if serde_json::to_value(&old_network.l2geth)?
+                == serde_json::to_value(&new_network.l2geth)?
+            {
+                self.networks[network_index]
+                    .read()
+                    .unwrap()
+                    .l2geth_client
+                    .clone()
+            } else {
+                match new_network.l2geth {
+                    Some(l2geth) => Some(Arc::new(L2gethClient::new(l2geth)?)),
+                    None => None,
+                }
+            };
+            info!(network = ?name, "Reloaded network config");
+            networks.push((
+                network_index,
+                Arc::new(Network {
+                    name,
+                    proof_types,
+                    l2geth_client,
+                }),
+            ));
+        }
+
+        for (network_index, network) in networks {
+            *self.networks[network_index].write().unwrap() = network;
+        }
+        for (i, coordinator_client) in coordinator_clients {
+            *self.coordinator_clients[i].write().unwrap() = coordinator_client;
+        }
+        *self.polling.write().unwrap() = new_cfg.prover.polling.clone();
+        *self.config.lock().unwrap() = new_cfg;
+        info!("Config reloaded");
+        Ok(())
+    }
+}
+
+/// Returns the paths of the fields that differ between `old` and `new` but can only change
+/// with a restart.
+fn non_reloadable_changes(old: &Config, new: &Config) -> anyhow::Result<Vec<String>> {
+    let mut old = serde_json::to_value(old)?;
+    let mut new = serde_json::to_value(new)?;
+    for value in [&mut old, &mut new] {
+        for path in LIVE_FIELDS {
+            mask_field(value, path);
+        }
+        if let Some(Value::Array(networks)) = value.get_mut("networks") {
+            for network in networks {
+                for field in LIVE_NETWORK_FIELDS {
+                    mask_field(network, &[*field]);
+                }
+            }
+        }
+    }
+    let mut changes = vec![];
+    diff_paths(String::new(), &old, &new, &mut changes);
+    Ok(changes)
+}
+
+fn mask_field(value: &mut Value, path: &[&str]) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut value = value;
+    for parent in parents {
+        match value.get_mut(*parent) {
+            Some(child) => value = child,
+            None => return,
+        }
+    }
+    if let Value::Object(object) = value {
+        object.insert(last.to_string(), Value::Null);
+    }
+}
+
+fn diff_paths(path: String, old: &Value, new: &Value, changes: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                diff_paths(
+                    format!("{path}.{key}"),
+                    old.get(key).unwrap_or(&Value::Null),
+                    new.get(key).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+        (Value::Array(old), Value::Array(new)) if old.len() == new.len() => {
+            for (i, (old, new)) in old.iter().zip(new).enumerate() {
+                diff_paths(format!("{path}[{i}]"), old, new, changes);
+            }
+        }
+        (old, new) if old != new => {
+            changes.push(path.trim_start_matches('.').to_string());
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_reloadable_changes() {
+        let old = Config::from_file("conf/config.json".to_string()).unwrap();
+
+        let mut new = old.clone();
+        new.coordinator.as_mut().unwrap().base_url = "http://other:8555".to_string();
+        new.prover.polling.no_task.interval_sec += 1;
+        new.prover.supported_proof_types.pop();
+        assert!(non_reloadable_changes(&old, &new).unwrap().is_empty());
+
+        new.keys_dir = "other_keys".to_string();
+        new.db_path = None;
+        assert_eq!(
+            non_reloadable_changes(&old, &new).unwrap(),
+            vec!["db_path".to_string(), "keys_dir".to_string()]
+        );
+    }
+}
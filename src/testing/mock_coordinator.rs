@@ -0,0 +1,520 @@
+use crate::{
+    config::CoordinatorConfig,
+    coordinator_handler::{
+        ChallengeResponseData, ErrorCode, GetTaskRequest, GetTaskResponseData, LoginRequest,
+        LoginResponseData, Response, SubmitProofRequest, SubmitProofResponseData,
+    },
+};
+use axum::{
+    extract::State,
+    http::{header::AUTHORIZATION, HeaderMap},
+    routing::{get, post},
+    Json, Router,
+};
+use ethers_core::{
+    k256::ecdsa::{RecoveryId, Signature, VerifyingKey},
+    utils::hex,
+};
+use jsonwebtoken::{errors::ErrorKind, DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tiny_keccak::{Hasher, Keccak};
+use tokio::sync::oneshot;
+
+const DEFAULT_TOKEN_TTL_SEC: u64 = 3600;
+
+/// A proof submission received by the mock coordinator.
+#[derive(Clone)]
+pub struct SubmittedProof {
+    pub prover_name: String,
+    pub request: SubmitProofRequest,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    exp: u64,
+    /// Tokens issued before the last `expire_tokens` call are rejected as expired.
+    generation: u64,
+    /// Set for login tokens, unset for challenge tokens.
+    prover_name: Option<String>,
+    random: String,
+}
+
+enum TokenError {
+    Expired,
+    Invalid(String),
+}
+
+struct MockState {
+    secret: Vec<u8>,
+    token_ttl: Duration,
+    generation: u64,
+    tasks: VecDeque<GetTaskResponseData>,
+    submissions: Vec<SubmittedProof>,
+    /// Number of upcoming submissions to answer with `InternalServerError`.
+    failing_submissions: usize,
+    logins: Vec<String>,
+}
+
+impl MockState {
+    fn issue_token(&self, prover_name: Option<String>) -> Response<LoginResponseData> {
+        let mut random = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut random);
+        let exp = SystemTime::now() + self.token_ttl;
+        let claims = Claims {
+            exp: exp.duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            generation: self.generation,
+            prover_name,
+            random: hex::encode(random),
+        };
+        match jsonwebtoken::encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(&self.secret),
+        ) {
+            Ok(token) => success(LoginResponseData {
+                time: format!("{}", claims.exp),
+                token,
+            }),
+            Err(e) => failure(ErrorCode::InternalServerError, e.to_string()),
+        }
+    }
+
+    fn verify_token(&self, headers: &HeaderMap) -> Result<Claims, TokenError> {
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| TokenError::Invalid("missing bearer token".to_string()))?;
+
+        let mut validation = Validation::default();
+        validation.leeway = 0;
+        let claims = jsonwebtoken::decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(&self.secret),
+            &validation,
+        )
+        .map_err(|e| match e.kind() {
+            ErrorKind::ExpiredSignature => TokenError::Expired,
+            _ => TokenError::Invalid(e.to_string()),
+        })?
+        .claims;
+
+        if claims.generation < self.generation {
+            return Err(TokenError::Expired);
+        }
+        Ok(claims)
+    }
+
+    /// Verifies a token carried by a logged in prover, returning its name.
+    fn authorize<T>(&self, headers: &HeaderMap) -> Result<String, Json<Response<T>>> {
+        match self.verify_token(headers) {
+            Ok(Claims {
+                prover_name: Some(prover_name),
+                ..
+            }) => Ok(prover_name),
+            Ok(_) => Err(Json(failure(
+                ErrorCode::ErrJWTCommonErr,
+                "challenge token cannot be used for this request".to_string(),
+            ))),
+            Err(e) => Err(Json(token_error(e))),
+        }
+    }
+}
+
+/// An in-process coordinator serving the prover-facing `/coordinator/v1` API on a random local
+/// port. Tasks are served in the order they are pushed, and every proof submission is recorded.
+///
+/// The server shuts down when the `MockCoordinator` is dropped.
+pub struct MockCoordinator {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockCoordinator {
+    pub async fn start() -> anyhow::Result<Self> {
+        let mut secret = vec![0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        let state = Arc::new(Mutex::new(MockState {
+            secret,
+            token_ttl: Duration::from_secs(DEFAULT_TOKEN_TTL_SEC),
+            generation: 0,
+            tasks: VecDeque::new(),
+            submissions: vec![],
+            failing_submissions: 0,
+            logins: vec![],
+        }));
+
+        let router = Router::new()
+            .route("/coordinator/v1/challenge", get(challenge))
+            .route("/coordinator/v1/login", post(login))
+            .route("/coordinator/v1/get_task", post(get_task))
+            .route("/coordinator/v1/submit_proof", post(submit_proof))
+            .with_state(state.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let server = axum::Server::from_tcp(listener)?
+            .serve(router.into_make_service())
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            });
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                log::error!("[mock coordinator] server error: {e}");
+            }
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Returns a client config pointing at this coordinator, with retries disabled so that
+    /// failures surface immediately.
+    pub fn config(&self) -> CoordinatorConfig {
+        CoordinatorConfig {
+            base_url: self.base_url(),
+            retry_count: 0,
+            retry_wait_time_sec: 1,
+            connection_timeout_sec: 10,
+        }
+    }
+
+    /// Queues a task to be handed out by `get_task` to the next prover asking for its type.
+    pub fn push_task(&self, task: GetTaskResponseData) {
+        self.state.lock().unwrap().tasks.push_back(task);
+    }
+
+    /// Returns the number of pushed tasks not handed out yet.
+    pub fn queued_tasks(&self) -> usize {
+        self.state.lock().unwrap().tasks.len()
+    }
+
+    pub fn submitted_proofs(&self) -> Vec<SubmittedProof> {
+        self.state.lock().unwrap().submissions.clone()
+    }
+
+    /// Makes the next `count` proof submissions fail with `InternalServerError` without being
+    /// recorded.
+    pub fn fail_submissions(&self, count: usize) {
+        self.state.lock().unwrap().failing_submissions = count;
+    }
+
+    /// Returns the prover names of all successful logins, in order.
+    pub fn logins(&self) -> Vec<String> {
+        self.state.lock().unwrap().logins.clone()
+    }
+
+    /// Sets the lifetime of tokens issued from now on.
+    pub fn set_token_ttl(&self, ttl: Duration) {
+        self.state.lock().unwrap().token_ttl = ttl;
+    }
+
+    /// Makes every token issued so far be rejected with `ErrJWTTokenExpired`.
+    pub fn expire_tokens(&self) {
+        self.state.lock().unwrap().generation += 1;
+    }
+}
+
+impl Drop for MockCoordinator {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+type SharedState = State<Arc<Mutex<MockState>>>;
+
+fn success<T>(data: T) -> Response<T> {
+    Response {
+        errcode: ErrorCode::Success,
+        errmsg: String::new(),
+        data: Some(data),
+    }
+}
+
+fn failure<T>(errcode: ErrorCode, errmsg: String) -> Response<T> {
+    Response {
+        errcode,
+        errmsg,
+        data: None,
+    }
+}
+
+fn token_error<T>(error: TokenError) -> Response<T> {
+    match error {
+        TokenError::Expired => failure(ErrorCode::ErrJWTTokenExpired, "token is expired".into()),
+        TokenError::Invalid(e) => failure(ErrorCode::ErrJWTCommonErr, e),
+    }
+}
+
+/// Checks that the login message was signed by the key in `public_key`, the same way the
+/// coordinator does.
+fn verify_login_signature(req: &LoginRequest) -> anyhow::Result<()> {
+    let public_key = hex::decode(req.public_key.trim_start_matches("0x"))?;
+    let public_key = VerifyingKey::from_sec1_bytes(&public_key)
+        .map_err(|e| anyhow::anyhow!("invalid public key: {e}"))?;
+
+    let signature = hex::decode(req.signature.trim_start_matches("0x"))?;
+    anyhow::ensure!(
+        signature.len() == 65,
+        "invalid signature length: {}",
+        signature.len()
+    );
+    let recovery_id = RecoveryId::from_byte(signature[64])
+        .ok_or_else(|| anyhow::anyhow!("invalid recovery id: {}", signature[64]))?;
+    let signature = Signature::from_slice(&signature[..64])
+        .map_err(|e| anyhow::anyhow!("invalid signature: {e}"))?;
+
+    let mut hash = [0u8; 32];
+    let mut hasher = Keccak::v256();
+    hasher.update(&rlp::encode(&req.message));
+    hasher.finalize(&mut hash);
+
+    let recovered = VerifyingKey::recover_from_prehash(&hash, &signature, recovery_id)
+        .map_err(|e| anyhow::anyhow!("failed to recover public key: {e}"))?;
+    anyhow::ensure!(
+        recovered == public_key,
+        "signature does not match public key"
+    );
+    Ok(())
+}
+
+async fn challenge(State(state): SharedState) -> Json<Response<ChallengeResponseData>> {
+    Json(state.lock().unwrap().issue_token(None))
+}
+
+async fn login(
+    State(state): SharedState,
+    headers: HeaderMap,
+    Json(req): Json<LoginRequest>,
+) -> Json<Response<LoginResponseData>> {
+    let mut state = state.lock().unwrap();
+    match state.verify_token(&headers) {
+        Ok(Claims {
+            prover_name: None, ..
+        }) => (),
+        Ok(_) => {
+            return Json(failure(
+                ErrorCode::ErrJWTCommonErr,
+                "login requires a challenge token".to_string(),
+            ))
+        }
+        Err(e) => return Json(token_error(e)),
+    }
+
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if bearer != Some(req.message.challenge.as_str()) {
+        return Json(failure(
+            ErrorCode::ErrJWTCommonErr,
+            "login message does not carry the issued challenge".to_string(),
+        ));
+    }
+    if let Err(e) = verify_login_signature(&req) {
+        return Json(failure(
+            ErrorCode::ErrCoordinatorParameterInvalidNo,
+            format!("failed to verify login signature: {e}"),
+        ));
+    }
+
+    let prover_name = req.message.prover_name.clone();
+    let response = state.issue_token(Some(prover_name.clone()));
+    if response.errcode == ErrorCode::Success {
+        state.logins.push(prover_name);
+    }
+    Json(response)
+}
+
+async fn get_task(
+    State(state): SharedState,
+    headers: HeaderMap,
+    Json(req): Json<GetTaskRequest>,
+) -> Json<Response<GetTaskResponseData>> {
+    let mut state = state.lock().unwrap();
+    if let Err(response) = state.authorize(&headers) {
+        return response;
+    }
+
+    let position = state
+        .tasks
+        .iter()
+        .position(|task| req.task_types.contains(&task.task_type));
+    match position.and_then(|i| state.tasks.remove(i)) {
+        Some(task) => Json(success(task)),
+        None => Json(failure(
+            ErrorCode::ErrCoordinatorEmptyProofData,
+            "no task available".to_string(),
+        )),
+    }
+}
+
+async fn submit_proof(
+    State(state): SharedState,
+    headers: HeaderMap,
+    Json(req): Json<SubmitProofRequest>,
+) -> Json<Response<SubmitProofResponseData>> {
+    let mut state = state.lock().unwrap();
+    let prover_name = match state.authorize(&headers) {
+        Ok(prover_name) => prover_name,
+        Err(response) => return response,
+    };
+
+    if state.failing_submissions > 0 {
+        state.failing_submissions -= 1;
+        return Json(failure(
+            ErrorCode::InternalServerError,
+            "submission failed".to_string(),
+        ));
+    }
+    state.submissions.push(SubmittedProof {
+        prover_name,
+        request: req,
+    });
+    Json(success(SubmitProofResponseData {}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        coordinator_handler::{CoordinatorClient, KeySigner, ProofStatus, ProverType},
+        prover::{ProofType, ProverProviderType},
+    };
+
+    fn new_client(coordinator: &MockCoordinator) -> CoordinatorClient {
+        let key_signer = KeySigner::new_from_secret_key(
+            "8b8df68fddf7ee2724b79ccbd07799909d59b4dd4f4df3f6ecdc4fb8d56bdf4c",
+        )
+        .unwrap();
+        CoordinatorClient::new(
+            coordinator.config(),
+            vec![ProverType::Batch],
+            vec!["mock_vk".to_string()],
+            "test_prover".to_string(),
+            ProverProviderType::External,
+            Arc::new(key_signer),
+        )
+        .unwrap()
+    }
+
+    fn get_task_request() -> GetTaskRequest {
+        GetTaskRequest {
+            task_types: vec![ProofType::Batch],
+            prover_height: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_coordinator_round_trip() {
+        let coordinator = MockCoordinator::start().await.unwrap();
+        let client = new_client(&coordinator);
+
+        let response = client.get_task(&get_task_request()).await.unwrap();
+        assert_eq!(response.errcode, ErrorCode::ErrCoordinatorEmptyProofData);
+        assert_eq!(coordinator.logins(), vec!["test_prover".to_string()]);
+
+        coordinator.push_task(GetTaskResponseData {
+            uuid: "uuid".to_string(),
+            task_id: "task".to_string(),
+            task_type: ProofType::Batch,
+            task_data: "{}".to_string(),
+            hard_fork_name: "euclid".to_string(),
+        });
+        let task = client.get_task(&get_task_request()).await.unwrap();
+        assert_eq!(task.errcode, ErrorCode::Success);
+        let task = task.data.unwrap();
+        assert_eq!(task.task_id, "task");
+
+        // An expired token is refreshed transparently by logging in again.
+        coordinator.expire_tokens();
+        let response = client
+            .submit_proof(&SubmitProofRequest {
+                uuid: task.uuid,
+                task_id: task.task_id,
+                task_type: task.task_type,
+                status: ProofStatus::Ok,
+                proof: "proof".to_string(),
+                failure_type: None,
+                failure_msg: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.errcode, ErrorCode::Success);
+        assert_eq!(coordinator.logins().len(), 2);
+
+        let submitted = coordinator.submitted_proofs();
+        assert_eq!(submitted.len(), 1);
+        assert_eq!(submitted[0].prover_name, "test_prover");
+        assert_eq!(submitted[0].request.proof, "proof");
+    }
+
+    #[tokio::test]
+    async fn test_mock_coordinator_rejects_bad_signature() {
+        let coordinator = MockCoordinator::start().await.unwrap();
+        let response = reqwest::get(format!(
+            "{}/coordinator/v1/challenge",
+            coordinator.base_url()
+        ))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+        let response: Response<ChallengeResponseData> = serde_json::from_str(&response).unwrap();
+        let challenge = response.data.unwrap().token;
+
+        let key_signer = KeySigner::new_from_secret_key(
+            "8b8df68fddf7ee2724b79ccbd07799909d59b4dd4f4df3f6ecdc4fb8d56bdf4c",
+        )
+        .unwrap();
+        let request = LoginRequest {
+            message: crate::coordinator_handler::LoginMessage {
+                challenge: challenge.clone(),
+                prover_version: "v0.0.0".to_string(),
+                prover_name: "test_prover".to_string(),
+                prover_provider_type: ProverProviderType::External,
+                prover_types: vec![ProverType::Batch],
+                vks: vec![],
+            },
+            public_key: key_signer.get_public_key(),
+            signature: key_signer.sign_buffer(b"something else").unwrap(),
+        };
+        let response = reqwest::Client::new()
+            .post(format!("{}/coordinator/v1/login", coordinator.base_url()))
+            .bearer_auth(challenge)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_string(&request).unwrap())
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        let response: Response<LoginResponseData> = serde_json::from_str(&response).unwrap();
+        assert_eq!(
+            response.errcode,
+            ErrorCode::ErrCoordinatorParameterInvalidNo
+        );
+        assert!(coordinator.logins().is_empty());
+    }
+}
@@ -0,0 +1,518 @@
+use crate::{
+    config::ProvingServiceConfig,
+    prover::{
+        http_proving_service::{
+            CreateTaskRequest, ErrorResponse, InputResponse, TaskResponse, VksRequest, VksResponse,
+        },
+        proving_service::{FailureKind, TaskStatus},
+        InputCompression,
+    },
+};
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_ENCODING},
+        HeaderMap, StatusCode,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use std::{
+    collections::HashMap,
+    io::Read,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::oneshot;
+
+struct MockState {
+    api_key: String,
+    vks: Vec<String>,
+    tasks: HashMap<String, TaskResponse>,
+    /// Proof to finish new tasks with right away, if any.
+    instant_proof: Option<String>,
+    requests: Vec<CreateTaskRequest>,
+    cancelled: Vec<String>,
+    /// Number of task status queries served.
+    status_queries: usize,
+    /// Uploaded inputs, decompressed, by id.
+    inputs: HashMap<String, String>,
+}
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ErrorResponse>)>;
+
+impl MockState {
+    fn authorize(&self, headers: &HeaderMap) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+        let bearer = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if bearer == Some(self.api_key.as_str()) {
+            Ok(())
+        } else {
+            Err(error(StatusCode::UNAUTHORIZED, "invalid api key"))
+        }
+    }
+}
+
+/// An in-process proving service implementing the REST protocol documented in
+/// [`http_proving_service`](crate::prover::http_proving_service) on a random local port.
+///
+/// New tasks stay queued until finished with [`finish_task`](Self::finish_task) or
+/// [`fail_task`](Self::fail_task), unless [`set_instant_proof`](Self::set_instant_proof) is used.
+/// The server shuts down when the `MockProvingService` is dropped.
+pub struct MockProvingService {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockProvingService {
+    pub async fn start(api_key: &str) -> anyhow::Result<Self> {
+        let state = Arc::new(Mutex::new(MockState {
+            api_key: api_key.to_string(),
+            vks: vec![],
+            tasks: HashMap::new(),
+            instant_proof: None,
+            requests: vec![],
+            cancelled: vec![],
+            status_queries: 0,
+            inputs: HashMap::new(),
+        }));
+
+        let router = Router::new()
+            .route("/v1/vks", post(vks))
+            .route("/v1/inputs", post(upload_input))
+            .route("/v1/tasks", post(create_task))
+            .route("/v1/tasks/:task_id", get(query_task))
+            .route("/v1/tasks/:task_id/cancel", post(cancel_task))
+            .route("/v1/health", get(health))
+            .layer(DefaultBodyLimit::disable())
+            .with_state(state.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let server = axum::Server::from_tcp(listener)?
+            .serve(router.into_make_service())
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            });
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                log::error!("[mock proving service] server error: {e}");
+            }
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Returns a client config pointing at this service, with retries disabled so that failures
+    /// surface immediately.
+    pub fn config(&self) -> ProvingServiceConfig {
+        let api_key = self.state.lock().unwrap().api_key.clone();
+        ProvingServiceConfig {
+            base_url: self.base_url(),
+            api_key,
+            retry_count: 0,
+            retry_wait_time_sec: 1,
+            connection_timeout_sec: 10,
+            upload_inputs: false,
+        }
+    }
+
+    pub fn set_vks(&self, vks: Vec<String>) {
+        self.state.lock().unwrap().vks = vks;
+    }
+
+    /// Makes tasks created from now on succeed immediately with `proof`, or stay queued if `None`.
+    pub fn set_instant_proof(&self, proof: Option<String>) {
+        self.state.lock().unwrap().instant_proof = proof;
+    }
+
+    /// Returns every accepted prove request, in order, with uploaded inputs filled in.
+    pub fn prove_requests(&self) -> Vec<CreateTaskRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Returns the ids of the tasks cancelled so far, in order.
+    pub fn cancelled_tasks(&self) -> Vec<String> {
+        self.state.lock().unwrap().cancelled.clone()
+    }
+
+    /// Returns how many times the status of a task was queried so far.
+    pub fn status_queries(&self) -> usize {
+        self.state.lock().unwrap().status_queries
+    }
+
+    pub fn set_task_status(&self, task_id: &str, status: TaskStatus) {
+        if let Some(task) = self.state.lock().unwrap().tasks.get_mut(task_id) {
+            task.status = status;
+        }
+    }
+
+    pub fn finish_task(&self, task_id: &str, proof: &str) {
+        if let Some(task) = self.state.lock().unwrap().tasks.get_mut(task_id) {
+            finish(task, proof.to_string());
+        }
+    }
+
+    pub fn fail_task(&self, task_id: &str, error: &str, failure_kind: Option<FailureKind>) {
+        if let Some(task) = self.state.lock().unwrap().tasks.get_mut(task_id) {
+            task.status = TaskStatus::Failed;
+            task.finished_at = Some(now());
+            task.error = Some(error.to_string());
+            task.failure_kind = failure_kind;
+        }
+    }
+}
+
+impl Drop for MockProvingService {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+type SharedState = State<Arc<Mutex<MockState>>>;
+
+fn error(status: StatusCode, error: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+        }),
+    )
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64()
+}
+
+fn finish(task: &mut TaskResponse, proof: String) {
+    let finished_at = now();
+    task.status = TaskStatus::Success;
+    task.started_at.get_or_insert(task.created_at);
+    task.finished_at = Some(finished_at);
+    task.compute_time_sec = Some(finished_at - task.created_at);
+    task.proof = Some(proof);
+}
+
+async fn vks(
+    State(state): SharedState,
+    headers: HeaderMap,
+    Json(_): Json<VksRequest>,
+) -> ApiResult<VksResponse> {
+    let state = state.lock().unwrap();
+    state.authorize(&headers)?;
+    Ok(Json(VksResponse {
+        vks: state.vks.clone(),
+    }))
+}
+
+async fn upload_input(
+    State(state): SharedState,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<InputResponse> {
+    let mut state = state.lock().unwrap();
+    state.authorize(&headers)?;
+
+    let compression = match headers.get(CONTENT_ENCODING).map(|value| value.as_bytes()) {
+        None => InputCompression::None,
+        Some(b"gzip") => InputCompression::Gzip,
+        Some(b"zstd") => InputCompression::Zstd,
+        Some(_) => {
+            return Err(error(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "unsupported content encoding",
+            ))
+        }
+    };
+    let mut input = String::new();
+    compression
+        .decoder(body.as_ref())
+        .and_then(|mut decoder| decoder.read_to_string(&mut input))
+        .map_err(|e| error(StatusCode::BAD_REQUEST, &e.to_string()))?;
+
+    let input_id = format!("input-{}", state.inputs.len());
+    state.inputs.insert(input_id.clone(), input);
+    Ok(Json(InputResponse { input_id }))
+}
+
+async fn create_task(
+    State(state): SharedState,
+    headers: HeaderMap,
+    Json(mut req): Json<CreateTaskRequest>,
+) -> ApiResult<TaskResponse> {
+    let mut state = state.lock().unwrap();
+    state.authorize(&headers)?;
+    if let Some(input_id) = &req.input_id {
+        req.input = state
+            .inputs
+            .get(input_id)
+            .cloned()
+            .ok_or_else(|| error(StatusCode::BAD_REQUEST, "input not found"))?;
+    }
+
+    let mut task = TaskResponse {
+        task_id: format!("task-{}", state.requests.len()),
+        proof_type: req.proof_type,
+        circuit_version: req.circuit_version.clone(),
+        hard_fork_name: req.hard_fork_name.clone(),
+        status: TaskStatus::Queued,
+        created_at: now(),
+        input: Some(req.input.clone()),
+        ..Default::default()
+    };
+    if let Some(proof) = state.instant_proof.clone() {
+        finish(&mut task, proof);
+    }
+    state.requests.push(req);
+    state.tasks.insert(task.task_id.clone(), task.clone());
+    Ok(Json(task))
+}
+
+async fn query_task(
+    State(state): SharedState,
+    headers: HeaderMap,
+    Path(task_id): Path<String>,
+) -> ApiResult<TaskResponse> {
+    let mut state = state.lock().unwrap();
+    state.authorize(&headers)?;
+    state.status_queries += 1;
+    state
+        .tasks
+        .get(&task_id)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "task not found"))
+}
+
+async fn cancel_task(
+    State(state): SharedState,
+    headers: HeaderMap,
+    Path(task_id): Path<String>,
+) -> ApiResult<TaskResponse> {
+    let mut state = state.lock().unwrap();
+    state.authorize(&headers)?;
+    let task = state
+        .tasks
+        .get_mut(&task_id)
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "task not found"))?;
+    if matches!(task.status, TaskStatus::Queued | TaskStatus::Proving) {
+        task.status = TaskStatus::Failed;
+        task.finished_at = Some(now());
+        task.error = Some("cancelled".to_string());
+    }
+    let task = task.clone();
+    state.cancelled.push(task_id);
+    Ok(Json(task))
+}
+
+async fn health(State(state): SharedState, headers: HeaderMap) -> ApiResult<()> {
+    state.lock().unwrap().authorize(&headers)?;
+    Ok(Json(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::InputConfig;
+    use crate::prover::{
+        proving_service::{CancelTaskRequest, GetVkRequest, ProveRequest, QueryTaskRequest},
+        HttpProvingService, ProofType, ProveInputWriter, ProvingService,
+    };
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn test_http_proving_service_round_trip() {
+        let service = MockProvingService::start("secret").await.unwrap();
+        service.set_vks(vec!["vk".to_string()]);
+        let client = HttpProvingService::new(service.config()).unwrap();
+
+        let vks = client
+            .get_vks(GetVkRequest {
+                proof_types: vec![ProofType::Batch],
+                circuit_version: "v0.13.1".to_string(),
+            })
+            .await;
+        assert!(vks.error.is_none());
+        assert_eq!(vks.vks, vec!["vk".to_string()]);
+        assert!(client.health_check().await.is_ok());
+
+        let task = client
+            .prove(ProveRequest {
+                proof_type: ProofType::Batch,
+                circuit_version: "v0.13.1".to_string(),
+                hard_fork_name: "euclid".to_string(),
+                input: "input".into(),
+            })
+            .await;
+        assert!(task.error.is_none());
+        assert_eq!(task.status, TaskStatus::Queued);
+        assert_eq!(service.prove_requests()[0].input, "input");
+
+        let query = QueryTaskRequest {
+            task_id: task.task_id.clone(),
+        };
+        service.finish_task(&task.task_id, "proof");
+        let task = client.query_task(query).await;
+        assert_eq!(task.status, TaskStatus::Success);
+        assert_eq!(task.proof.as_deref(), Some("proof"));
+
+        let missing = client
+            .query_task(QueryTaskRequest {
+                task_id: "missing".to_string(),
+            })
+            .await;
+        assert_eq!(missing.status, TaskStatus::Failed);
+        assert!(missing.error.unwrap().contains("task not found"));
+        assert_eq!(missing.failure_kind, Some(FailureKind::Transient));
+    }
+
+    #[tokio::test]
+    async fn test_http_proving_service_uploads_input() {
+        let service = MockProvingService::start("secret").await.unwrap();
+        let client = HttpProvingService::new(ProvingServiceConfig {
+            upload_inputs: true,
+            ..service.config()
+        })
+        .unwrap();
+
+        let content = "trace,".repeat(1000);
+        let mut writer = ProveInputWriter::new(&InputConfig {
+            compression: InputCompression::Zstd,
+            spool_threshold_bytes: 0,
+            spool_dir: None,
+        })
+        .unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+        let task = client
+            .prove(ProveRequest {
+                proof_type: ProofType::Chunk,
+                input: writer.finish().unwrap(),
+                ..Default::default()
+            })
+            .await;
+        assert!(task.error.is_none());
+        let requests = service.prove_requests();
+        assert_eq!(requests[0].input_id.as_deref(), Some("input-0"));
+        assert_eq!(requests[0].input, content);
+    }
+
+    #[tokio::test]
+    async fn test_http_proving_service_streams_embedded_input() {
+        let service = MockProvingService::start("secret").await.unwrap();
+        let client = HttpProvingService::new(service.config()).unwrap();
+
+        let content = "{\"trace\": \"a\\b\"}\n\t\u{1}ü,".repeat(20_000);
+        let mut writer = ProveInputWriter::new(&InputConfig {
+            compression: InputCompression::Gzip,
+            spool_threshold_bytes: 0,
+            spool_dir: None,
+        })
+        .unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+        let task = client
+            .prove(ProveRequest {
+                proof_type: ProofType::Chunk,
+                input: writer.finish().unwrap(),
+                ..Default::default()
+            })
+            .await;
+        assert!(task.error.is_none());
+        let requests = service.prove_requests();
+        assert!(requests[0].input_id.is_none());
+        assert_eq!(requests[0].input, content);
+    }
+
+    #[tokio::test]
+    async fn test_http_proving_service_reports_failure_kind() {
+        let service = MockProvingService::start("secret").await.unwrap();
+        let client = HttpProvingService::new(service.config()).unwrap();
+
+        let task = client.prove(ProveRequest::default()).await;
+        service.fail_task(&task.task_id, "oom", Some(FailureKind::OutOfMemory));
+        let task = client
+            .query_task(QueryTaskRequest {
+                task_id: task.task_id,
+            })
+            .await;
+        assert_eq!(task.status, TaskStatus::Failed);
+        assert_eq!(task.failure_kind, Some(FailureKind::OutOfMemory));
+
+        let unreachable = HttpProvingService::new(ProvingServiceConfig {
+            base_url: "http://127.0.0.1:1".to_string(),
+            ..service.config()
+        })
+        .unwrap();
+        let task = unreachable.prove(ProveRequest::default()).await;
+        assert_eq!(task.status, TaskStatus::Failed);
+        assert_eq!(task.failure_kind, Some(FailureKind::Transient));
+        let query = unreachable
+            .query_task(QueryTaskRequest {
+                task_id: "task-0".to_string(),
+            })
+            .await;
+        assert_eq!(query.status, TaskStatus::Unknown);
+        assert!(query.failure_kind.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_http_proving_service_cancels_task() {
+        let service = MockProvingService::start("secret").await.unwrap();
+        let client = HttpProvingService::new(service.config()).unwrap();
+
+        let task = client.prove(ProveRequest::default()).await;
+        client
+            .cancel_task(CancelTaskRequest {
+                task_id: task.task_id.clone(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(service.cancelled_tasks(), vec![task.task_id.clone()]);
+        let task = client
+            .query_task(QueryTaskRequest {
+                task_id: task.task_id,
+            })
+            .await;
+        assert_eq!(task.status, TaskStatus::Failed);
+
+        let missing = client
+            .cancel_task(CancelTaskRequest {
+                task_id: "missing".to_string(),
+            })
+            .await;
+        assert!(missing.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_http_proving_service_rejects_bad_api_key() {
+        let service = MockProvingService::start("secret").await.unwrap();
+        let client = HttpProvingService::new(ProvingServiceConfig {
+            api_key: "wrong".to_string(),
+            ..service.config()
+        })
+        .unwrap();
+
+        let vks = client.get_vks(GetVkRequest::default()).await;
+        assert!(vks.error.unwrap().contains("invalid api key"));
+        assert!(client.health_check().await.is_err());
+    }
+}
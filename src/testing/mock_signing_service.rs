@@ -0,0 +1,209 @@
+use crate::{
+    config::RemoteSignerConfig,
+    coordinator_handler::{remote_signer::SignRequest, KeySigner},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::post,
+    Json, Router,
+};
+use ethers_core::{
+    types::H256,
+    utils::{hex, keccak256},
+};
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+};
+use tokio::sync::oneshot;
+
+struct MockState {
+    keys: HashMap<String, KeySigner>,
+    /// Identifiers of the keys asked to sign, in order.
+    requests: Vec<String>,
+}
+
+/// An in-process signing service implementing the protocol documented in
+/// [`remote_signer`](crate::coordinator_handler::remote_signer) on a random local port.
+///
+/// The server shuts down when the `MockSigningService` is dropped.
+pub struct MockSigningService {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockSigningService {
+    pub async fn start() -> anyhow::Result<Self> {
+        let state = Arc::new(Mutex::new(MockState {
+            keys: HashMap::new(),
+            requests: vec![],
+        }));
+
+        let router = Router::new()
+            .route("/api/v1/eth1/sign/:identifier", post(sign))
+            .with_state(state.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let server = axum::Server::from_tcp(listener)?
+            .serve(router.into_make_service())
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            });
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                log::error!("[mock signing service] server error: {e}");
+            }
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Returns a client config for the given keys, with retries disabled so that failures
+    /// surface immediately.
+    pub fn config(&self, public_keys: Vec<String>) -> RemoteSignerConfig {
+        RemoteSignerConfig {
+            base_url: self.base_url(),
+            public_keys,
+            retry_count: 0,
+            retry_wait_time_sec: 1,
+            connection_timeout_sec: 10,
+        }
+    }
+
+    /// Serves signing requests for `identifier` with `key`.
+    pub fn add_key(&self, identifier: &str, key: KeySigner) {
+        self.state
+            .lock()
+            .unwrap()
+            .keys
+            .insert(identifier.to_string(), key);
+    }
+
+    /// Returns the identifier of every key asked to sign, in order.
+    pub fn sign_requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockSigningService {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn sign(
+    State(state): State<Arc<Mutex<MockState>>>,
+    Path(identifier): Path<String>,
+    Json(req): Json<SignRequest>,
+) -> Result<String, (StatusCode, String)> {
+    let mut state = state.lock().unwrap();
+    state.requests.push(identifier.clone());
+    let key = state
+        .keys
+        .get(&identifier)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "unknown key".to_string()))?;
+
+    let message = hex::decode(req.data.trim_start_matches("0x"))
+        .map_err(|_| (StatusCode::BAD_REQUEST, "invalid data".to_string()))?;
+    let signature = key
+        .sign_hash(H256::from(keccak256(message)))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(format!("0x{}", hex::encode(signature.to_vec())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        coordinator_handler::{
+            CoordinatorClient, ErrorCode, GetTaskRequest, ProverType, RemoteSigner, Signer,
+        },
+        prover::{ProofType, ProverProviderType},
+        testing::MockCoordinator,
+    };
+
+    const SECRET_KEY: &str = "8b8df68fddf7ee2724b79ccbd07799909d59b4dd4f4df3f6ecdc4fb8d56bdf4c";
+
+    #[tokio::test]
+    async fn test_remote_signer_login() {
+        let key_signer = KeySigner::new_from_secret_key(SECRET_KEY).unwrap();
+        let public_key = key_signer.get_public_key();
+        let signing_service = MockSigningService::start().await.unwrap();
+        signing_service.add_key(&public_key, key_signer.clone());
+
+        let signer = RemoteSigner::new(
+            &signing_service.config(vec![public_key.clone()]),
+            &public_key,
+        )
+        .unwrap();
+        assert_eq!(Signer::get_public_key(&signer), public_key);
+        assert_eq!(
+            signer.sign_buffer(b"message").await.unwrap(),
+            key_signer.sign_buffer(b"message").unwrap()
+        );
+
+        let coordinator = MockCoordinator::start().await.unwrap();
+        let client = CoordinatorClient::new(
+            coordinator.config(),
+            vec![ProverType::Batch],
+            vec![],
+            "remote_prover".to_string(),
+            ProverProviderType::External,
+            Arc::new(signer),
+        )
+        .unwrap();
+        let response = client
+            .get_task(&GetTaskRequest {
+                task_types: vec![ProofType::Batch],
+                prover_height: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.errcode, ErrorCode::ErrCoordinatorEmptyProofData);
+        assert_eq!(coordinator.logins(), vec!["remote_prover".to_string()]);
+        assert_eq!(signing_service.sign_requests(), vec![public_key]);
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_rejects_foreign_signature() {
+        let key_signer = KeySigner::new_from_secret_key(SECRET_KEY).unwrap();
+        let public_key = key_signer.get_public_key();
+        let signing_service = MockSigningService::start().await.unwrap();
+        let other_key = KeySigner::new_from_secret_key(
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+        )
+        .unwrap();
+        signing_service.add_key(&public_key, other_key);
+
+        let signer = RemoteSigner::new(
+            &signing_service.config(vec![public_key.clone()]),
+            &public_key,
+        )
+        .unwrap();
+        let error = signer.sign_buffer(b"message").await.unwrap_err();
+        assert!(error.to_string().contains("does not match"));
+
+        let unknown = RemoteSigner::new(
+            &signing_service.config(vec![]),
+            &format!("0x{}", public_key),
+        )
+        .unwrap();
+        assert!(unknown.sign_buffer(b"message").await.is_err());
+    }
+}
@@ -0,0 +1,10 @@
+//! Test doubles for exercising provers without a live Scroll stack. Enabled by the `testing`
+//! feature.
+
+mod mock_coordinator;
+mod mock_proving_service;
+mod mock_signing_service;
+
+pub use mock_coordinator::{MockCoordinator, SubmittedProof};
+pub use mock_proving_service::MockProvingService;
+pub use mock_signing_service::MockSigningService;
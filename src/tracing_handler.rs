@@ -1,43 +1,323 @@
-use crate::config::L2GethConfig;
+use crate::{config::L2GethConfig, metrics};
 use ethers_core::types::BlockNumber;
 use ethers_core::types::H256;
-use ethers_providers::{Http, Provider};
+use ethers_providers::{Http, Provider, ProviderError, RpcError};
+use futures::{future::join_all, stream, StreamExt, TryStreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt::Debug,
+    future::Future,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::time::{error::Elapsed, timeout};
 pub type CommonHash = H256;
 
+/// An l2geth endpoint along with what the client last learned about it.
+pub struct L2gethEndpoint {
+    pub url: String,
+    pub provider: Provider<Http>,
+    healthy: AtomicBool,
+    /// Unix time of the last failed request, if the endpoint is unhealthy.
+    failed_at: AtomicU64,
+    /// Latest block height reported by the endpoint, `0` if unknown.
+    block_height: AtomicU64,
+}
+
+impl L2gethEndpoint {
+    fn new(url: String) -> anyhow::Result<Self> {
+        let provider = Provider::<Http>::try_from(url.as_str())?;
+        Ok(Self {
+            url,
+            provider,
+            healthy: AtomicBool::new(true),
+            failed_at: AtomicU64::new(0),
+            block_height: AtomicU64::new(0),
+        })
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    pub fn block_height(&self) -> u64 {
+        self.block_height.load(Ordering::Relaxed)
+    }
+
+    /// Whether the endpoint failed less than `cooldown` ago, and should be tried last.
+    fn is_demoted(&self, cooldown: Duration) -> bool {
+        !self.is_healthy()
+            && unix_now().saturating_sub(self.failed_at.load(Ordering::Relaxed))
+                < cooldown.as_secs()
+    }
+
+    fn record_result<T>(&self, result: &anyhow::Result<T>) {
+        let healthy = result.is_ok();
+        if !healthy {
+            self.failed_at.store(unix_now(), Ordering::Relaxed);
+        }
+        if self.healthy.swap(healthy, Ordering::Relaxed) != healthy {
+            if healthy {
+                log::info!("l2geth endpoint {} is healthy again", self.url);
+            } else {
+                log::warn!("l2geth endpoint {} is unhealthy", self.url);
+            }
+        }
+    }
+}
+
 pub struct L2gethClient {
+    /// Provider of the primary `endpoint`. Requests sent through it directly do not fail over to
+    /// the fallback endpoints.
     pub provider: Provider<Http>,
+    endpoints: Vec<L2gethEndpoint>,
+    prefer_highest_block: bool,
+    request_timeout: Duration,
+    trace_request_timeout: Duration,
+    unhealthy_cooldown: Duration,
+    trace_fetch_concurrency: usize,
+    retry_count: u32,
+    retry_wait_time_sec: u64,
 }
 
 impl L2gethClient {
     pub fn new(cfg: L2GethConfig) -> anyhow::Result<Self> {
-        let provider = Provider::<Http>::try_from(cfg.endpoint)?;
-        Ok(Self { provider })
+        let endpoints = std::iter::once(cfg.endpoint)
+            .chain(cfg.fallback_endpoints)
+            .map(L2gethEndpoint::new)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self {
+            provider: endpoints[0].provider.clone(),
+            endpoints,
+            prefer_highest_block: cfg.prefer_highest_block,
+            request_timeout: Duration::from_secs(cfg.request_timeout_sec),
+            trace_request_timeout: Duration::from_secs(cfg.trace_request_timeout_sec),
+            unhealthy_cooldown: Duration::from_secs(cfg.unhealthy_cooldown_sec),
+            trace_fetch_concurrency: cfg.trace_fetch_concurrency.max(1),
+            retry_count: cfg.retry_count,
+            retry_wait_time_sec: cfg.retry_wait_time_sec,
+        })
+    }
+
+    pub fn endpoints(&self) -> &[L2gethEndpoint] {
+        &self.endpoints
+    }
+
+    /// The number of block traces fetched at once for a single task.
+    pub fn trace_fetch_concurrency(&self) -> usize {
+        self.trace_fetch_concurrency
+    }
+
+    /// Endpoints in the order they should be tried: those that did not fail within the unhealthy
+    /// cooldown first, then by block height if `prefer_highest_block` is set, then in configured
+    /// order. Endpoints are tried again once their cooldown passes, so that recovered ones are
+    /// promoted back.
+    fn ordered_endpoints(&self) -> Vec<&L2gethEndpoint> {
+        let mut endpoints: Vec<&L2gethEndpoint> = self.endpoints.iter().collect();
+        endpoints.sort_by_key(|endpoint| {
+            let height = if self.prefer_highest_block {
+                endpoint.block_height()
+            } else {
+                0
+            };
+            (
+                endpoint.is_demoted(self.unhealthy_cooldown),
+                std::cmp::Reverse(height),
+            )
+        });
+        endpoints
+    }
+
+    /// Runs `f` against each endpoint in turn until one succeeds within the request timeout,
+    /// returning the last error if none does.
+    pub async fn with_failover<T, F, Fut>(&self, method: &str, f: F) -> anyhow::Result<T>
+    where
+        F: Fn(Provider<Http>) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        self.failover(method, self.request_timeout, f).await
+    }
+
+    /// Like [`with_failover`](Self::with_failover), with the longer timeout of block trace and
+    /// witness requests.
+    pub async fn with_trace_failover<T, F, Fut>(&self, method: &str, f: F) -> anyhow::Result<T>
+    where
+        F: Fn(Provider<Http>) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        self.failover(method, self.trace_request_timeout, f).await
+    }
+
+    async fn failover<T, F, Fut>(
+        &self,
+        method: &str,
+        request_timeout: Duration,
+        f: F,
+    ) -> anyhow::Result<T>
+    where
+        F: Fn(Provider<Http>) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let mut last_error = None;
+        for endpoint in self.ordered_endpoints() {
+            let result = match timeout(request_timeout, f(endpoint.provider.clone())).await {
+                Ok(result) => result,
+                Err(elapsed) => Err(anyhow::Error::from(elapsed)),
+            };
+            endpoint.record_result(&result);
+            match result {
+                Ok(value) => return Ok(value),
+                Err(e) => {
+                    log::warn!(
+                        "l2geth_client {method} failed on endpoint {}, err: {e}",
+                        endpoint.url
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("no l2geth endpoint configured")))
     }
 
+    async fn request<P, R>(
+        &self,
+        method: &str,
+        request_timeout: Duration,
+        params: P,
+    ) -> anyhow::Result<R>
+    where
+        P: Serialize + Debug + Clone + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        self.failover(method, request_timeout, |provider| {
+            let params = params.clone();
+            async move {
+                let started = Instant::now();
+                let result = provider
+                    .request::<_, R>(method, params)
+                    .await
+                    .map_err(anyhow::Error::from);
+                metrics::observe_l2geth_request(method, started, &result);
+                result
+            }
+        })
+        .await
+    }
+
+    /// Fetches a block trace, retrying transient failures up to `retry_count` times. Each attempt
+    /// fails over across all endpoints, so a trace can be requested `retry_count + 1` times per
+    /// endpoint. Retries stop once they would run past the time that retrying a single endpoint
+    /// which times out on every attempt takes, so more endpoints do not mean longer waits.
     pub async fn get_block_trace_by_hash(&self, hash: &CommonHash) -> anyhow::Result<String> {
+        let retries = self.retry_count as u64;
+        let deadline = Instant::now()
+            + self.trace_request_timeout * (self.retry_count + 1)
+            + Duration::from_secs(self.retry_wait_time_sec * retries * (retries + 1) / 2);
+        let mut attempt = 0;
+        loop {
+            let result = self.request_block_trace_by_hash(hash).await;
+            let wait = Duration::from_secs(self.retry_wait_time_sec * (attempt as u64 + 1));
+            match result {
+                Err(e)
+                    if attempt < self.retry_count
+                        && is_transient(&e)
+                        && Instant::now() + wait < deadline =>
+                {
+                    attempt += 1;
+                    log::warn!(
+                        "l2geth_client get_block_trace_by_hash failed, hash: {hash:#x}, attempt: {attempt}, err: {e}"
+                    );
+                    tokio::time::sleep(wait).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn request_block_trace_by_hash(&self, hash: &CommonHash) -> anyhow::Result<String> {
         log::info!(
             "l2geth_client calling get_block_trace_by_hash, hash: {:#?}",
             hash
         );
 
         let trace: serde_json::Value = self
-            .provider
-            .request("scroll_getBlockTraceByNumberOrHash", [format!("{hash:#x}")])
+            .request(
+                "scroll_getBlockTraceByNumberOrHash",
+                self.trace_request_timeout,
+                [format!("{hash:#x}")],
+            )
             .await?;
-
         let trace = serde_json::to_string(&trace)?;
         Ok(trace)
     }
 
+    /// Returns the current block number. With `prefer_highest_block`, every endpoint is asked
+    /// and the highest answer is returned.
     pub async fn block_number(&self) -> anyhow::Result<BlockNumber> {
         log::info!("l2geth_client calling block_number");
 
-        let block_number = self.provider.request("eth_blockNumber", ()).await?;
+        let block_number = if self.prefer_highest_block {
+            self.refresh_block_heights().await?
+        } else {
+            self.request::<_, BlockNumber>("eth_blockNumber", self.request_timeout, ())
+                .await?
+        };
         log::info!("l2geth_client received block_number: {:#?}", block_number);
 
         Ok(block_number)
     }
 
+    /// Asks every endpoint for its block height, returning the highest one.
+    async fn refresh_block_heights(&self) -> anyhow::Result<BlockNumber> {
+        let method = "eth_blockNumber";
+        let results = join_all(self.endpoints.iter().map(|endpoint| async move {
+            let started = Instant::now();
+            let result = match timeout(
+                self.request_timeout,
+                endpoint.provider.request::<_, BlockNumber>(method, ()),
+            )
+            .await
+            {
+                Ok(result) => result.map_err(anyhow::Error::from),
+                Err(elapsed) => Err(anyhow::Error::from(elapsed)),
+            };
+            metrics::observe_l2geth_request(method, started, &result);
+            endpoint.record_result(&result);
+            if let Some(height) = result.as_ref().ok().and_then(BlockNumber::as_number) {
+                endpoint
+                    .block_height
+                    .store(height.as_u64(), Ordering::Relaxed);
+            }
+            result
+        }))
+        .await;
+
+        let mut last_error = None;
+        let mut highest = None;
+        for result in results {
+            match result {
+                Ok(block_number) => {
+                    let height = block_number.as_number().map(|n| n.as_u64());
+                    if highest.as_ref().is_none_or(|(h, _)| height > *h) {
+                        highest = Some((height, block_number));
+                    }
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        match highest {
+            Some((_, block_number)) => Ok(block_number),
+            None => {
+                Err(last_error.unwrap_or_else(|| anyhow::anyhow!("no l2geth endpoint configured")))
+            }
+        }
+    }
+
+    /// Fetches the block traces into memory, in the order of `block_hashes`.
+    #[deprecated(
+        note = "holds all traces in memory; fetch them one by one with `get_block_trace_by_hash`, \
+                as chunk inputs are streamed"
+    )]
     pub async fn get_traces_by_hashes(
         &self,
         block_hashes: &[CommonHash],
@@ -47,12 +327,72 @@ impl L2gethClient {
             anyhow::bail!("block_hashes are empty")
         }
 
-        let mut block_traces = Vec::new();
-        for hash in block_hashes.iter() {
-            let trace = self.get_block_trace_by_hash(hash).await?;
-            block_traces.push(trace);
-        }
+        // `buffered` yields the traces in the order of `block_hashes`.
+        stream::iter(block_hashes)
+            .map(|hash| self.get_block_trace_by_hash(hash))
+            .buffered(self.trace_fetch_concurrency)
+            .try_collect()
+            .await
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Whether a failed request may succeed when sent again. JSON-RPC error responses and malformed
+/// responses are returned by l2geth as-is on retry.
+fn is_transient(error: &anyhow::Error) -> bool {
+    if error.is::<Elapsed>() {
+        return true;
+    }
+    match error.downcast_ref::<ProviderError>() {
+        Some(e) => e.as_error_response().is_none() && e.as_serde_error().is_none(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unhealthy_endpoint_is_tried_again_after_cooldown() {
+        let client = |unhealthy_cooldown_sec: u64| {
+            L2gethClient::new(
+                serde_json::from_value(serde_json::json!({
+                    "endpoint": "http://primary:8545",
+                    "fallback_endpoints": ["http://secondary:8545"],
+                    "unhealthy_cooldown_sec": unhealthy_cooldown_sec,
+                }))
+                .unwrap(),
+            )
+            .unwrap()
+        };
+        let urls = |client: &L2gethClient| -> Vec<String> {
+            client
+                .ordered_endpoints()
+                .into_iter()
+                .map(|endpoint| endpoint.url.clone())
+                .collect()
+        };
 
-        Ok(block_traces)
+        let demoting = client(3600);
+        demoting.endpoints[0].record_result::<()>(&Err(anyhow::anyhow!("down")));
+        assert_eq!(
+            urls(&demoting),
+            vec!["http://secondary:8545", "http://primary:8545"]
+        );
+
+        let recovering = client(0);
+        recovering.endpoints[0].record_result::<()>(&Err(anyhow::anyhow!("down")));
+        assert!(!recovering.endpoints[0].is_healthy());
+        assert_eq!(
+            urls(&recovering),
+            vec!["http://primary:8545", "http://secondary:8545"]
+        );
     }
 }